@@ -0,0 +1,155 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Index, LitInt, Member};
+
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    std140(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    vertex(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<Vec<(Member, syn::Type)>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "only structs can be derived"));
+    };
+
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter()
+            .map(|field| (Member::Named(field.ident.clone().unwrap()), field.ty.clone()))
+            .collect(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().enumerate()
+            .map(|(index, field)| (Member::Unnamed(Index::from(index)), field.ty.clone()))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    Ok(fields)
+}
+
+fn std140(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let fields = struct_fields(input)?;
+    let std140 = quote!(::renderia::rendering::rgl::std140);
+
+    let members = fields.iter().map(|(member, _)| member);
+    let types: Vec<_> = fields.iter().map(|(_, ty)| ty).collect();
+
+    Ok(quote! {
+        impl #impl_generics #std140::Std140 for #name #type_generics #where_clause {
+            const ALIGN: usize = #std140::struct_align(&[#(<#types as #std140::Std140>::ALIGN),*]);
+            const SIZE: usize = {
+                let mut offset = 0;
+                #(offset = #std140::align_to(offset, <#types as #std140::Std140>::ALIGN) + <#types as #std140::Std140>::SIZE;)*
+                #std140::align_to(offset, Self::ALIGN)
+            };
+
+            fn write_std140(&self, out: &mut #std140::Std140Writer) {
+                let start = out.len();
+                #(out.write(&self.#members);)*
+                out.pad_to(start + Self::SIZE);
+            }
+        }
+    })
+}
+
+#[derive(Default)]
+struct VertexFieldOptions {
+    location: Option<u32>,
+    normalized: bool,
+    float: bool,
+    skip: bool,
+}
+
+fn vertex_field_options(field: &syn::Field) -> syn::Result<VertexFieldOptions> {
+    let mut options = VertexFieldOptions::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("location") {
+                options.location = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("normalized") {
+                options.normalized = true;
+            } else if meta.path.is_ident("float") {
+                options.float = true;
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error("expected `location = N`, `normalized`, `float` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+fn has_repr_c(input: &DeriveInput) -> bool {
+    input.attrs.iter().filter(|attr| attr.path().is_ident("repr")).any(|attr| {
+        let mut repr_c = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+            Ok(())
+        });
+        repr_c
+    })
+}
+
+fn vertex(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let vertex = quote!(::renderia::rendering::vertex);
+
+    if !has_repr_c(input) {
+        return Err(Error::new_spanned(name, "Vertex can only be derived for #[repr(C)] structs"));
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "only structs can be derived"));
+    };
+
+    let mut attributes = Vec::new();
+    // Locations are built up as const expressions, since how many a field takes depends on its type.
+    let mut next_location = quote!(0);
+    for (index, field) in data.fields.iter().enumerate() {
+        let options = vertex_field_options(field)?;
+        if options.skip {
+            continue;
+        }
+
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+        let ty = &field.ty;
+        let location = match options.location {
+            Some(location) => quote!(#location),
+            None => next_location,
+        };
+        let normalized = options.normalized;
+        let as_float = options.normalized || options.float;
+        next_location = quote!((#location + <#ty as #vertex::VertexComponent>::LOCATIONS));
+
+        attributes.push(quote! {
+            #vertex::VertexAttributeLayout {
+                location: #location,
+                components: <#ty as #vertex::VertexComponent>::COMPONENTS,
+                gl_type: <#ty as #vertex::VertexComponent>::GL_TYPE,
+                kind: #vertex::AttributeKind::resolve(<#ty as #vertex::VertexComponent>::KIND, #as_float),
+                normalized: #normalized,
+                offset: ::core::mem::offset_of!(#name #type_generics, #member),
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics #vertex::Vertex for #name #type_generics #where_clause {
+            const ATTRIBUTES: &'static [#vertex::VertexAttributeLayout] = &[#(#attributes),*];
+        }
+    })
+}
@@ -1,4 +1,5 @@
 use glam::{Mat4, Quat, Vec3, vec3};
+use crate::errors::ShaderError;
 use crate::rendering::rgl::ShaderProgram;
 use crate::rendering::rgl::UniformValue::UniformMatrix4F;
 
@@ -23,17 +24,17 @@ impl PerspectiveCamera {
         PerspectiveCamera { pos, rotation, fov, perspective, width, height, near, far }
     }
 
-    pub fn apply_vm_to_uniform(&self, uniform_name: &str, program: &mut ShaderProgram) {
+    pub fn apply_vm_to_uniform(&self, uniform_name: &str, program: &mut ShaderProgram) -> Result<(), ShaderError> {
         if !program.has_uniform(uniform_name) {
-            program.create_uniform(uniform_name);
+            program.create_uniform(uniform_name)?;
         }
 
         program.set_uniform(uniform_name, UniformMatrix4F { value: self.view_matrix() })
     }
 
-    pub fn apply_pm_to_uniform(&self, uniform_name: &str, program: &mut ShaderProgram) {
+    pub fn apply_pm_to_uniform(&self, uniform_name: &str, program: &mut ShaderProgram) -> Result<(), ShaderError> {
         if !program.has_uniform(uniform_name) {
-            program.create_uniform(uniform_name);
+            program.create_uniform(uniform_name)?;
         }
 
         program.set_uniform(uniform_name, UniformMatrix4F { value: self.perspective.clone() })
@@ -1,7 +1,113 @@
+use std::io;
+use std::path::PathBuf;
+use image::{ColorType, ImageError};
 use thiserror::Error;
+use crate::rendering::rgl::ShaderStage;
+use crate::rendering::rgl::diagnostics::{CompileFailure, LinkFailure};
+use crate::rendering::rgl::vertex_input::VertexInputIssue;
 
 #[derive(Error, Debug)]
 pub enum Errors {
     #[error("Renderia test error.")]
     TestError,
-}
\ No newline at end of file
+    #[error(transparent)]
+    Shader(#[from] ShaderError),
+    #[error(transparent)]
+    Texture(#[from] TextureError),
+    #[error(transparent)]
+    Window(#[from] WindowError),
+    #[error(transparent)]
+    Buffer(#[from] BufferError),
+}
+
+#[derive(Error, Debug)]
+pub enum ShaderError {
+    #[error("Failed to read shader {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("Malformed include directive in {file}:{line}: {directive}")]
+    InvalidInclude { file: String, line: u32, directive: String },
+    #[error("Cannot resolve include \"{include}\" from {file}:{line}")]
+    IncludeNotFound { file: String, line: u32, include: String },
+    #[error("Include cycle detected: {}", display_paths(.0))]
+    IncludeCycle(Vec<PathBuf>),
+    #[error("Invalid shader stage combination: {0}")]
+    InvalidStages(String),
+    #[error("{stage} shader source contains a nul byte")]
+    NulInSource { stage: ShaderStage },
+    #[error("Failed to compile {} shader:\n{0}", .0.stage)]
+    Compile(CompileFailure),
+    #[error("Failed to link shader program:\n{0}")]
+    Link(LinkFailure),
+    #[error("Cannot locate uniform: {0}")]
+    UniformNotFound(String),
+    #[error("Cannot locate shader storage block: {0}")]
+    StorageBlockNotFound(String),
+    #[error("Program has no compute stage and cannot be dispatched")]
+    NotComputeProgram,
+    #[error("Uniform {name} has room for {available} elements but was given {given}")]
+    UniformArrayOverflow { name: String, available: usize, given: usize },
+    #[error("Uniform {0} is not a sampler")]
+    NotASampler(String),
+    #[error("Sampler {name} is a {found} and cannot sample a 2D texture")]
+    SamplerTypeMismatch { name: String, found: &'static str },
+    #[error("Cannot locate uniform block: {0}")]
+    UniformBlockNotFound(String),
+    #[error("Uniform block {name} needs {expected} bytes but the buffer only holds {found}")]
+    UniformBlockSizeMismatch { name: String, expected: usize, found: usize },
+    #[error("Uniform {name} is declared as {expected} but was given a {found}")]
+    UniformTypeMismatch { name: String, expected: &'static str, found: &'static str },
+    #[error("Vertex attributes do not match the shader inputs: {}", display_issues(.0))]
+    VertexInputMismatch(Vec<VertexInputIssue>),
+}
+
+fn display_issues(issues: &[VertexInputIssue]) -> String {
+    issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(" -> ")
+}
+
+#[derive(Error, Debug)]
+pub enum TextureError {
+    #[error("Failed to open texture {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("Failed to decode texture: {0}")]
+    Decode(#[from] ImageError),
+    #[error("Unsupported color format: {0:?}")]
+    UnsupportedColorType(ColorType),
+}
+
+#[derive(Error, Debug)]
+pub enum WindowError {
+    #[error("Failed to initialize GLFW: {0}")]
+    Init(#[from] glfw::InitError),
+    #[error("Failed to create window!")]
+    Creation,
+    #[error("Failed to load OpenGL functions for the window context")]
+    Context,
+}
+
+#[derive(Error, Debug)]
+pub enum BufferError {
+    #[error("Range {start}..{end} is out of bounds for buffer of {len} elements")]
+    OutOfBounds { start: usize, end: usize, len: usize },
+    #[error("Failed to map buffer {0}")]
+    MapFailed(u32),
+    #[error("Cannot map an empty range")]
+    EmptyRange,
+    #[error("A stream buffer of {regions} regions of {region_size} bytes holds nothing")]
+    EmptyStreamBuffer { region_size: usize, regions: usize },
+    #[error("Vertex stride must not be zero")]
+    ZeroStride,
+    #[error("{size} bytes of vertex data is not a whole number of {stride}-byte vertices")]
+    PartialVertex { size: usize, stride: usize },
+    #[error("Index {index} does not fit in indices of at most {max}")]
+    IndexOutOfRange { index: u32, max: u32 },
+    #[error("Mesh has no index buffer")]
+    NotIndexed,
+    #[error("All {limit} uniform buffer binding points are in use")]
+    BindingsExhausted { limit: u32 },
+    #[error("{0} is not supported by the current context")]
+    Unsupported(&'static str),
+}
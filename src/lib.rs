@@ -1,3 +1,5 @@
+extern crate self as renderia;
+
 pub mod logger;
 pub mod errors;
 pub mod rendering;
@@ -9,103 +11,89 @@ pub fn add(left: usize, right: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use std::mem;
-    use gl::types::{GLfloat, GLint, GLsizei};
-    use glam::{Mat4, Quat, vec3};
+    use std::rc::Rc;
+    use bytemuck::{Pod, Zeroable};
+    use gl::types::GLint;
+    use glam::{Mat4, Quat, Vec2, Vec3, vec3};
     use crate::camera::PerspectiveCamera;
+    use crate::errors::Errors;
     use crate::rendering::Renderer;
-    use crate::rendering::rgl::{ShaderProgram, VertexAttribute};
+    use crate::rendering::instancing::InstanceBuffer;
+    use crate::rendering::mesh::Mesh;
+    use crate::rendering::pipeline::{CompareFunc, DepthState, PipelineState};
+    use crate::rendering::rgl::ShaderProgram;
     use crate::rendering::rgl::UniformValue::UniformMatrix4F;
     use crate::rendering::texture::*;
+    use crate::rendering::vertex::Vertex;
     use crate::rendering::window::Window;
     use super::*;
 
-    #[test]
-    fn it_works() -> Result<(), String> {
-        let mut window = Window::new(800, 800, "Hello Window!");
-        window.init_gl();
-
-        let mut renderer = Renderer::new();
-        renderer.bind();
-        renderer.with_attribute(VertexAttribute::new(
-            0,
-            3,
-            gl::FLOAT,
-            false,
-            5 * mem::size_of::<GLfloat>() as GLsizei,
-            0
-        ));
-        renderer.with_attribute(VertexAttribute::new(
-            1,
-            2,
-            gl::FLOAT,
-            false,
-            5 * mem::size_of::<GLfloat>() as GLsizei,
-            3 * mem::size_of::<GLfloat>() as i32
-        ));
-        renderer.enable_attributes();
-
-        let vertices = [
-            -0.5, -0.5, -0.5,  0.0, 0.0,
-            0.5, -0.5, -0.5,  1.0, 0.0,
-            0.5,  0.5, -0.5,  1.0, 1.0,
-            0.5,  0.5, -0.5,  1.0, 1.0,
-            -0.5,  0.5, -0.5,  0.0, 1.0,
-            -0.5, -0.5, -0.5,  0.0, 0.0,
-
-            -0.5, -0.5,  0.5,  0.0, 0.0,
-            0.5, -0.5,  0.5,  1.0, 0.0,
-            0.5,  0.5,  0.5,  1.0, 1.0,
-            0.5,  0.5,  0.5,  1.0, 1.0,
-            -0.5,  0.5,  0.5,  0.0, 1.0,
-            -0.5, -0.5,  0.5,  0.0, 0.0,
-
-            -0.5,  0.5,  0.5,  1.0, 0.0,
-            -0.5,  0.5, -0.5,  1.0, 1.0,
-            -0.5, -0.5, -0.5,  0.0, 1.0,
-            -0.5, -0.5, -0.5,  0.0, 1.0,
-            -0.5, -0.5,  0.5,  0.0, 0.0,
-            -0.5,  0.5,  0.5,  1.0, 0.0,
-
-            0.5,  0.5,  0.5,  1.0, 0.0,
-            0.5,  0.5, -0.5,  1.0, 1.0,
-            0.5, -0.5, -0.5,  0.0, 1.0,
-            0.5, -0.5, -0.5,  0.0, 1.0,
-            0.5, -0.5,  0.5,  0.0, 0.0,
-            0.5,  0.5,  0.5,  1.0, 0.0,
-
-            -0.5, -0.5, -0.5,  0.0, 1.0,
-            0.5, -0.5, -0.5,  1.0, 1.0,
-            0.5, -0.5,  0.5,  1.0, 0.0,
-            0.5, -0.5,  0.5,  1.0, 0.0,
-            -0.5, -0.5,  0.5,  0.0, 0.0,
-            -0.5, -0.5, -0.5,  0.0, 1.0,
-
-            -0.5,  0.5, -0.5,  0.0, 1.0,
-            0.5,  0.5, -0.5,  1.0, 1.0,
-            0.5,  0.5,  0.5,  1.0, 0.0,
-            0.5,  0.5,  0.5,  1.0, 0.0,
-            -0.5,  0.5,  0.5,  0.0, 0.0,
-            -0.5,  0.5, -0.5,  0.0, 1.0
-        ];
-
-        let cube_positions = [
-            vec3( 0.0,  0.0,  0.0),
-            vec3( 2.0,  5.0, -15.0),
-            vec3(-1.5, -2.2, -2.5),
-            vec3(-3.8, -2.0, -12.3),
-            vec3( 2.4, -0.4, -3.5),
-            vec3(-1.7,  3.0, -7.5),
-            vec3( 1.3, -2.0, -2.5),
-            vec3( 1.5,  2.0, -2.5),
-            vec3( 1.5,  0.2, -1.5),
-            vec3(-1.3,  1.0, -1.5)
-        ];
-
-        let mut shaders = ShaderProgram::new("test_shaders/shader.vsh", "test_shaders/shader.fsh");
-        shaders.bind();
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+    struct CubeVertex {
+        position: Vec3,
+        uv: Vec2,
+    }
 
-        let mut texture = Texture::from("test_shaders/wall.jpg").unwrap();
+    const CUBE_VERTICES: [f32; 180] = [
+        -0.5, -0.5, -0.5,  0.0, 0.0,
+        0.5, -0.5, -0.5,  1.0, 0.0,
+        0.5,  0.5, -0.5,  1.0, 1.0,
+        0.5,  0.5, -0.5,  1.0, 1.0,
+        -0.5,  0.5, -0.5,  0.0, 1.0,
+        -0.5, -0.5, -0.5,  0.0, 0.0,
+
+        -0.5, -0.5,  0.5,  0.0, 0.0,
+        0.5, -0.5,  0.5,  1.0, 0.0,
+        0.5,  0.5,  0.5,  1.0, 1.0,
+        0.5,  0.5,  0.5,  1.0, 1.0,
+        -0.5,  0.5,  0.5,  0.0, 1.0,
+        -0.5, -0.5,  0.5,  0.0, 0.0,
+
+        -0.5,  0.5,  0.5,  1.0, 0.0,
+        -0.5,  0.5, -0.5,  1.0, 1.0,
+        -0.5, -0.5, -0.5,  0.0, 1.0,
+        -0.5, -0.5, -0.5,  0.0, 1.0,
+        -0.5, -0.5,  0.5,  0.0, 0.0,
+        -0.5,  0.5,  0.5,  1.0, 0.0,
+
+        0.5,  0.5,  0.5,  1.0, 0.0,
+        0.5,  0.5, -0.5,  1.0, 1.0,
+        0.5, -0.5, -0.5,  0.0, 1.0,
+        0.5, -0.5, -0.5,  0.0, 1.0,
+        0.5, -0.5,  0.5,  0.0, 0.0,
+        0.5,  0.5,  0.5,  1.0, 0.0,
+
+        -0.5, -0.5, -0.5,  0.0, 1.0,
+        0.5, -0.5, -0.5,  1.0, 1.0,
+        0.5, -0.5,  0.5,  1.0, 0.0,
+        0.5, -0.5,  0.5,  1.0, 0.0,
+        -0.5, -0.5,  0.5,  0.0, 0.0,
+        -0.5, -0.5, -0.5,  0.0, 1.0,
+
+        -0.5,  0.5, -0.5,  0.0, 1.0,
+        0.5,  0.5, -0.5,  1.0, 1.0,
+        0.5,  0.5,  0.5,  1.0, 0.0,
+        0.5,  0.5,  0.5,  1.0, 0.0,
+        -0.5,  0.5,  0.5,  0.0, 0.0,
+        -0.5,  0.5, -0.5,  0.0, 1.0
+    ];
+
+    const CUBE_POSITIONS: [Vec3; 10] = [
+        vec3( 0.0,  0.0,  0.0),
+        vec3( 2.0,  5.0, -15.0),
+        vec3(-1.5, -2.2, -2.5),
+        vec3(-3.8, -2.0, -12.3),
+        vec3( 2.4, -0.4, -3.5),
+        vec3(-1.7,  3.0, -7.5),
+        vec3( 1.3, -2.0, -2.5),
+        vec3( 1.5,  2.0, -2.5),
+        vec3( 1.5,  0.2, -1.5),
+        vec3(-1.3,  1.0, -1.5)
+    ];
+
+    fn wall_texture() -> Result<Texture, Errors> {
+        let mut texture = Texture::from("test_shaders/wall.jpg")?;
         texture.bind();
         texture.with_parameter(TextureParameter::new_i(
             gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::MIRRORED_REPEAT as GLint
@@ -120,23 +108,32 @@ mod tests {
             gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint
         ));
         texture.apply_parameters();
+        Ok(texture)
+    }
 
-        let camera = PerspectiveCamera::new(vec3(0.0, 0.0, -8.0), Quat::default(), 45.0, 800.0, 800.0, 0.1, 100.0);
-        shaders.create_uniform("model");
-        shaders.create_uniform("view");
+    #[test]
+    fn it_works() -> Result<(), Errors> {
+        let mut window = Window::new(800, 800, "Hello Window!")?;
+        window.init_gl()?;
 
-        unsafe {
-            gl::Enable(gl::DEPTH_TEST)
-        }
+        let cube_vertices = bytemuck::cast_slice::<f32, CubeVertex>(&CUBE_VERTICES);
+        let mut cube = Mesh::from_vertices(gl::TRIANGLES, cube_vertices)?;
+        cube.with_pipeline(PipelineState::new().with_depth(DepthState::enabled(CompareFunc::Less)));
+
+        let mut shaders = ShaderProgram::new("test_shaders/shader.vsh", "test_shaders/shader.fsh")?;
+        cube.validate(&shaders)?;
+        shaders.bind();
+        shaders.bind_texture("texture0", Rc::new(wall_texture()?), 0)?;
+
+        let camera = PerspectiveCamera::new(vec3(0.0, 0.0, -8.0), Quat::default(), 45.0, 800.0, 800.0, 0.1, 100.0);
 
         while !window.should_close() {
             Renderer::clear_color(0.3, 0.5, 0.3, 1.0);
             Renderer::clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
-            camera.apply_pm_to_uniform("projection", &mut shaders);
-            camera.apply_vm_to_uniform("view", &mut shaders);
+            camera.apply_pm_to_uniform("projection", &mut shaders)?;
+            camera.apply_vm_to_uniform("view", &mut shaders)?;
 
-            renderer.bind();
 
             //let mut view = Mat4::IDENTITY;
             //let translate = Mat4::from_translation(vec3(0.0, 0.0, -3.0));
@@ -145,14 +142,14 @@ mod tests {
 
 
             let mut i = 0.0;
-            for pos in &cube_positions {
+            for pos in &CUBE_POSITIONS {
                 let mut model = Mat4::IDENTITY;
                 let transformation = Mat4::from_translation(*pos);
                 model *= transformation;
                 println!("{model}");
 
-                shaders.set_uniform("model", UniformMatrix4F { value: model });
-                renderer.draw_arrays(gl::TRIANGLES, vertices.to_vec(), 36);
+                shaders.set_uniform("model", UniformMatrix4F { value: model })?;
+                cube.draw();
                 i += 1.0;
             }
 
@@ -161,4 +158,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn draws_instanced_cubes() -> Result<(), Errors> {
+        let mut window = Window::new(800, 800, "Instanced cubes")?;
+        window.init_gl()?;
+
+        let cube_vertices = bytemuck::cast_slice::<f32, CubeVertex>(&CUBE_VERTICES);
+        let mut cube = Mesh::from_vertices(gl::TRIANGLES, cube_vertices)?;
+
+        let cube_models: Vec<Mat4> = CUBE_POSITIONS.iter().map(|pos| Mat4::from_translation(*pos)).collect();
+        let instances = InstanceBuffer::new(&cube_models);
+        cube.with_instance_matrix(&instances, 2, 0);
+        cube.with_pipeline(PipelineState::new().with_depth(DepthState::enabled(CompareFunc::Less)));
+
+        let mut shaders = ShaderProgram::new("test_shaders/instanced.vsh", "test_shaders/shader.fsh")?;
+        cube.validate(&shaders)?;
+        shaders.bind();
+        shaders.bind_texture("texture0", Rc::new(wall_texture()?), 0)?;
+
+        let camera = PerspectiveCamera::new(vec3(0.0, 0.0, -8.0), Quat::default(), 45.0, 800.0, 800.0, 0.1, 100.0);
+
+        while !window.should_close() {
+            Renderer::clear_color(0.3, 0.5, 0.3, 1.0);
+            Renderer::clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            camera.apply_pm_to_uniform("projection", &mut shaders)?;
+            camera.apply_vm_to_uniform("view", &mut shaders)?;
+            cube.draw_instanced(cube_models.len());
+
+            window.update();
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,140 @@
+use std::mem;
+use bytemuck::Pod;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::rendering::mesh::Mesh;
+use crate::rendering::rgl::index_buffer::IndexBuffer;
+use crate::rendering::rgl::indirect::{DrawElementsIndirectCommand, IndirectBuffer};
+
+/// Identifies a mesh added to a `BatchBuilder`; it is also the index of the mesh's draw command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BatchedMesh(usize);
+
+impl BatchedMesh {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// Packs many small meshes into one shared vertex arena and one index arena, recording an indirect
+/// draw command per mesh. Indices stay local to their mesh and are offset with `base_vertex`, so
+/// the index type only has to fit the largest mesh rather than the whole arena.
+pub struct BatchBuilder<V: Pod> {
+    vertices: Vec<V>,
+    indices: Vec<u32>,
+    commands: Vec<DrawElementsIndirectCommand>,
+    largest_mesh: usize,
+}
+
+impl<V: Pod> BatchBuilder<V> {
+    pub fn new() -> BatchBuilder<V> {
+        BatchBuilder { vertices: Vec::new(), indices: Vec::new(), commands: Vec::new(), largest_mesh: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Appends a mesh drawn once, using `base_instance` equal to its handle so per-instance
+    /// attributes can carry per-mesh data.
+    pub fn add_mesh(&mut self, vertices: &[V], indices: &[u32]) -> BatchedMesh {
+        let handle = BatchedMesh(self.commands.len());
+        self.commands.push(DrawElementsIndirectCommand {
+            count: indices.len() as GLuint,
+            instance_count: 1,
+            first_index: self.indices.len() as GLuint,
+            base_vertex: self.vertices.len() as GLint,
+            base_instance: handle.0 as GLuint,
+        });
+
+        self.vertices.extend_from_slice(vertices);
+        self.indices.extend_from_slice(indices);
+        self.largest_mesh = self.largest_mesh.max(vertices.len());
+        handle
+    }
+
+    /// Uploads the arenas and commands. Vertex attributes are then added to `Batch::mesh_mut` as for
+    /// any other mesh. Fails if a mesh has an index past the vertices of the largest mesh.
+    pub fn build(self, draw_mode: GLenum) -> Result<Batch, BufferError> {
+        let mut mesh = Mesh::new(draw_mode, &self.vertices, mem::size_of::<V>())?;
+        mesh.with_index_buffer(IndexBuffer::compact(&self.indices, self.largest_mesh, gl::STATIC_DRAW)?);
+
+        Ok(Batch { mesh, commands: IndirectBuffer::new(&self.commands), command_list: self.commands })
+    }
+}
+
+impl<V: Pod> Default for BatchBuilder<V> {
+    fn default() -> BatchBuilder<V> {
+        BatchBuilder::new()
+    }
+}
+
+pub struct Batch {
+    mesh: Mesh,
+    commands: IndirectBuffer<DrawElementsIndirectCommand>,
+    command_list: Vec<DrawElementsIndirectCommand>,
+}
+
+impl Batch {
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        &mut self.mesh
+    }
+
+    pub fn commands(&self) -> &[DrawElementsIndirectCommand] {
+        &self.command_list
+    }
+
+    /// Draws `mesh` `instance_count` times, reading per-instance data from `base_instance` onwards.
+    /// An instance count of zero hides the mesh without rebuilding the batch. Fails if `mesh` is not
+    /// part of the batch.
+    pub fn set_instances(&mut self, mesh: BatchedMesh, instance_count: GLuint, base_instance: GLuint) -> Result<(), BufferError> {
+        let len = self.command_list.len();
+        let command = self.command_list.get_mut(mesh.0)
+            .ok_or(BufferError::OutOfBounds { start: mesh.0, end: mesh.0 + 1, len })?;
+        command.instance_count = instance_count;
+        command.base_instance = base_instance;
+
+        self.commands.update(mesh.0, &[*command])
+    }
+
+    /// Draws every mesh in the batch with a single `glMultiDrawElementsIndirect` call.
+    pub fn draw(&self) {
+        let Some(index_type) = self.mesh.index_type() else { return };
+
+        self.mesh.apply_pipeline();
+        self.mesh.bind();
+        self.mesh.apply_primitive_restart();
+        self.commands.multi_draw_elements(self.mesh.draw_mode(), index_type);
+        self.mesh.unbind();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_meshes_into_shared_arenas() {
+        let mut builder = BatchBuilder::<[f32; 2]>::new();
+        let quad = builder.add_mesh(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]], &[0, 1, 2, 2, 3, 0]);
+        let triangle = builder.add_mesh(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]], &[0, 1, 2]);
+
+        assert_eq!((quad, triangle), (BatchedMesh(0), BatchedMesh(1)));
+        assert_eq!(builder.commands[1], DrawElementsIndirectCommand {
+            count: 3,
+            instance_count: 1,
+            first_index: 6,
+            base_vertex: 4,
+            base_instance: 1,
+        });
+        assert_eq!(builder.largest_mesh, 4);
+    }
+}
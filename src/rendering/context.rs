@@ -0,0 +1,185 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::logger::warn;
+use crate::rendering::state;
+use crate::rendering::tracker::ResourceKind;
+
+static NEXT_CONTEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static CURRENT_CONTEXT: Cell<Option<u64>> = const { Cell::new(None) };
+    static LIVE_CONTEXTS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    static CONTEXT_CAPABILITIES: RefCell<HashMap<u64, Capabilities>> = RefCell::new(HashMap::new());
+    static CURRENT_CAPABILITIES: Cell<Capabilities> = const { Cell::new(Capabilities {
+        direct_state_access: false,
+        vertex_attrib_binding: false,
+        buffer_storage: false,
+    }) };
+}
+
+/// Optional features of a context beyond the GL 3.3 core baseline the wrappers fall back to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// GL 4.5 direct state access: objects are created with `glCreate*` and edited by name
+    /// instead of being bound first.
+    pub direct_state_access: bool,
+    /// GL 4.3 separate attribute formats, read from binding points set with `glBindVertexBuffer`.
+    pub vertex_attrib_binding: bool,
+    /// GL 4.4 immutable buffer storage, which persistently mapped buffers need.
+    pub buffer_storage: bool,
+}
+
+impl Capabilities {
+    /// Queries the context that is current on this thread. GL functions must already be loaded.
+    pub fn detect() -> Capabilities {
+        let (mut major, mut minor) = (0, 0);
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        }
+
+        let version = (major, minor);
+        Capabilities {
+            direct_state_access: version >= (4, 5) && gl::CreateBuffers::is_loaded(),
+            vertex_attrib_binding: version >= (4, 3) && gl::VertexAttribBinding::is_loaded(),
+            buffer_storage: version >= (4, 4) && gl::BufferStorage::is_loaded(),
+        }
+    }
+}
+
+pub(crate) fn allocate_context_id() -> u64 {
+    NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+pub(crate) fn make_current(context_id: u64) {
+    LIVE_CONTEXTS.with(|contexts| {
+        let mut contexts = contexts.borrow_mut();
+        if !contexts.contains(&context_id) {
+            contexts.push(context_id);
+        }
+    });
+    CURRENT_CONTEXT.with(|current| current.set(Some(context_id)));
+    let capabilities = CONTEXT_CAPABILITIES.with(|capabilities| capabilities.borrow().get(&context_id).copied().unwrap_or_default());
+    CURRENT_CAPABILITIES.with(|current| current.set(capabilities));
+}
+
+pub(crate) fn set_capabilities(context_id: u64, capabilities: Capabilities) {
+    CONTEXT_CAPABILITIES.with(|all| all.borrow_mut().insert(context_id, capabilities));
+    if current_context() == Some(context_id) {
+        CURRENT_CAPABILITIES.with(|current| current.set(capabilities));
+    }
+}
+
+pub(crate) fn destroy(context_id: u64) {
+    LIVE_CONTEXTS.with(|contexts| contexts.borrow_mut().retain(|id| *id != context_id));
+    CONTEXT_CAPABILITIES.with(|capabilities| capabilities.borrow_mut().remove(&context_id));
+    state::forget_context(context_id);
+    CURRENT_CONTEXT.with(|current| {
+        if current.get() == Some(context_id) {
+            current.set(None);
+            CURRENT_CAPABILITIES.with(|current| current.set(Capabilities::default()));
+        }
+    });
+}
+
+pub fn current_context() -> Option<u64> {
+    CURRENT_CONTEXT.with(Cell::get)
+}
+
+pub fn is_alive(context_id: u64) -> bool {
+    LIVE_CONTEXTS.with(|contexts| contexts.borrow().contains(&context_id))
+}
+
+pub fn capabilities() -> Capabilities {
+    CURRENT_CAPABILITIES.with(Cell::get)
+}
+
+/// Whether the wrappers use direct state access on the current context rather than bind-to-edit.
+pub fn supports_dsa() -> bool {
+    capabilities().direct_state_access
+}
+
+/// Remembers the context a GL object was created in so its `Drop` only deletes it while that
+/// context is still alive and current on this thread. Holding one makes the owner `!Send`.
+#[derive(Debug)]
+pub(crate) struct ContextGuard {
+    context_id: Option<u64>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ContextGuard {
+    pub(crate) fn current() -> ContextGuard {
+        ContextGuard { context_id: current_context(), _not_send: PhantomData }
+    }
+
+    pub(crate) fn is_current(&self) -> bool {
+        self.context_id.is_some_and(|context_id| current_context() == Some(context_id))
+    }
+
+    /// Whether the object's context was destroyed, taking the object with it.
+    pub(crate) fn is_destroyed(&self) -> bool {
+        !self.context_id.is_some_and(is_alive)
+    }
+
+    /// Whether the object can be deleted now. Objects whose context was destroyed are already gone
+    /// with it; objects whose context is alive but not current are leaked with a warning, since
+    /// deleting them would free whatever shares their name in the current context. Leaked objects
+    /// stay in the tracker so they show up in its leak report.
+    pub(crate) fn can_delete(&self, kind: ResourceKind, id: u32) -> bool {
+        let Some(context_id) = self.context_id else { return false };
+        if !is_alive(context_id) {
+            return false;
+        }
+        if current_context() != Some(context_id) {
+            warn!("Leaking {} {} dropped while its context is not current", kind, id);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_follow_context_lifetime() {
+        assert!(!ContextGuard::current().can_delete(ResourceKind::Buffer, 1));
+
+        let first = allocate_context_id();
+        let second = allocate_context_id();
+        make_current(first);
+        let guard = ContextGuard::current();
+        assert!(guard.can_delete(ResourceKind::Buffer, 1));
+
+        make_current(second);
+        assert!(!guard.can_delete(ResourceKind::Buffer, 1));
+
+        make_current(first);
+        assert!(!guard.is_destroyed());
+        destroy(first);
+        assert!(!is_alive(first));
+        assert!(guard.is_destroyed());
+        assert_eq!(current_context(), None);
+        assert!(!guard.can_delete(ResourceKind::Buffer, 1));
+    }
+
+    #[test]
+    fn capabilities_follow_current_context() {
+        let modern = allocate_context_id();
+        let legacy = allocate_context_id();
+        make_current(modern);
+        set_capabilities(modern, Capabilities { direct_state_access: true, ..Capabilities::default() });
+        assert!(supports_dsa());
+
+        make_current(legacy);
+        assert!(!supports_dsa());
+
+        make_current(modern);
+        assert!(supports_dsa());
+        destroy(modern);
+        assert!(!supports_dsa());
+    }
+}
@@ -0,0 +1,41 @@
+use bytemuck::Pod;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::rendering::rgl::buffer::Buffer;
+
+/// Per-instance data such as model matrices, read by attributes with a divisor of 1. Attach it to a
+/// mesh with `Mesh::with_instance_attribute` or `Mesh::with_instance_matrix`.
+pub struct InstanceBuffer<T: Pod> {
+    buffer: Buffer<T>,
+}
+
+impl<T: Pod> InstanceBuffer<T> {
+    pub fn new(instances: &[T]) -> InstanceBuffer<T> {
+        InstanceBuffer { buffer: Buffer::from_slice(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW, instances) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.buffer.id()
+    }
+
+    /// Replaces every instance, reallocating storage for the same buffer object if more room is needed.
+    pub fn set_instances(&mut self, instances: &[T]) {
+        self.buffer.set_data(instances);
+    }
+
+    pub fn update(&self, first: usize, instances: &[T]) -> Result<(), BufferError> {
+        self.buffer.write(first, instances)
+    }
+}
@@ -0,0 +1,299 @@
+use std::ffi::c_void;
+use std::mem;
+use bytemuck::Pod;
+use glam::Vec4;
+use gl::types::*;
+use crate::errors::{BufferError, ShaderError};
+use crate::rendering::instancing::InstanceBuffer;
+use crate::rendering::rgl::{BufferObject, ShaderProgram, Vao, VertexAttribute};
+use crate::rendering::rgl::index_buffer::{Index, IndexBuffer, IndexType};
+use crate::rendering::pipeline::PipelineState;
+use crate::rendering::state;
+use crate::rendering::vertex::{Vertex, VertexAttributeLayout};
+
+/// Geometry that lives on the GPU. Vertex and index data are uploaded when the mesh is built and
+/// only touched again through the `update_*`/`set_*` methods, so drawing it costs no transfers.
+pub struct Mesh {
+    vao: Vao,
+    vertex_buffer: BufferObject,
+    index_buffer: Option<IndexBuffer>,
+    attributes: Vec<VertexAttribute>,
+    draw_mode: GLenum,
+    stride: usize,
+    vertex_count: usize,
+    primitive_restart: bool,
+    pipeline: Option<PipelineState>,
+}
+
+impl Mesh {
+    /// `stride` is the size of one vertex in bytes. Vertices may be any plain-data type, from a flat
+    /// `f32` slice to a `#[repr(C)]` vertex struct, as long as they make up whole vertices.
+    pub fn new<V: Pod>(draw_mode: GLenum, vertices: &[V], stride: usize) -> Result<Mesh, BufferError> {
+        check_vertices(mem::size_of_val(vertices), stride)?;
+
+        let vao = Vao::new();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW);
+
+        vertex_buffer.store_bytes(bytemuck::cast_slice(vertices));
+
+        Ok(Mesh {
+            vao,
+            vertex_buffer,
+            index_buffer: None,
+            attributes: Vec::new(),
+            draw_mode,
+            stride,
+            vertex_count: mem::size_of_val(vertices) / stride,
+            primitive_restart: false,
+            pipeline: None,
+        })
+    }
+
+    /// Builds a mesh from vertices with a derived layout, setting up every attribute of `V`.
+    pub fn from_vertices<V: Vertex>(draw_mode: GLenum, vertices: &[V]) -> Result<Mesh, BufferError> {
+        let mut mesh = Mesh::new(draw_mode, vertices, V::stride())?;
+        for layout in V::ATTRIBUTES {
+            mesh.with_layout(layout);
+        }
+        Ok(mesh)
+    }
+
+    /// Adds an attribute of any kind described by `layout`.
+    pub fn with_layout(&mut self, layout: &VertexAttributeLayout) {
+        let attribute = VertexAttribute::from_layout(layout, self.stride as GLsizei);
+        attribute.apply_to(&self.vao, &self.vertex_buffer);
+
+        self.attributes.push(attribute);
+    }
+
+    /// Adds a float attribute read from `offset` bytes into each vertex.
+    pub fn with_attribute(&mut self, index: GLuint, size: GLint, r#type: GLenum, normalized: bool, offset: usize) {
+        let attribute = VertexAttribute::new(index, size, r#type, normalized, self.stride as GLsizei, offset as i32);
+        attribute.apply_to(&self.vao, &self.vertex_buffer);
+
+        self.attributes.push(attribute);
+    }
+
+    /// Adds an attribute that advances once per instance, read from `offset` bytes into each element
+    /// of `instances`.
+    pub fn with_instance_attribute<T: Pod>(&mut self, instances: &InstanceBuffer<T>, index: GLuint, size: GLint, r#type: GLenum, normalized: bool, offset: usize) {
+        let attribute = VertexAttribute::new(index, size, r#type, normalized, mem::size_of::<T>() as GLsizei, offset as i32)
+            .with_divisor(1);
+        attribute.apply_to(&self.vao, instances.buffer().buffer());
+
+        self.attributes.push(attribute);
+    }
+
+    /// Adds a per-instance `mat4` attribute found at `offset` bytes into each instance. A matrix
+    /// attribute occupies four consecutive locations, one per column, starting at `index`.
+    pub fn with_instance_matrix<T: Pod>(&mut self, instances: &InstanceBuffer<T>, index: GLuint, offset: usize) {
+        for column in 0..4 {
+            let column_offset = offset + column * mem::size_of::<Vec4>();
+            self.with_instance_attribute(instances, index + column as GLuint, 4, gl::FLOAT, false, column_offset);
+        }
+    }
+
+    /// Switches the mesh to indexed drawing, storing the indices in the smallest type that can
+    /// address every vertex of the mesh. Fails if an index does not fit in that type.
+    pub fn with_indices(&mut self, indices: &[u32]) -> Result<(), BufferError> {
+        self.with_index_buffer(IndexBuffer::compact(indices, self.vertex_count, gl::STATIC_DRAW)?);
+        Ok(())
+    }
+
+    /// Switches the mesh to indexed drawing with indices of exactly type `I`.
+    pub fn with_typed_indices<I: Index>(&mut self, indices: &[I]) {
+        self.with_index_buffer(IndexBuffer::new(indices, gl::STATIC_DRAW));
+    }
+
+    pub fn with_index_buffer(&mut self, index_buffer: IndexBuffer) {
+        self.vao.bind_element_buffer(index_buffer.buffer());
+
+        self.index_buffer = Some(index_buffer);
+    }
+
+    /// Treats the largest value of the mesh's index type (see `IndexType::restart_index`) as the end
+    /// of a strip or fan, so several of them can be drawn with a single call.
+    pub fn with_primitive_restart(&mut self, enabled: bool) {
+        self.primitive_restart = enabled;
+    }
+
+    /// Draws the mesh with `pipeline`'s render state. Without one the mesh draws with whatever state
+    /// the previous draw left behind.
+    pub fn with_pipeline(&mut self, pipeline: PipelineState) {
+        self.pipeline = Some(pipeline);
+    }
+
+    pub fn pipeline(&self) -> Option<&PipelineState> {
+        self.pipeline.as_ref()
+    }
+
+    /// Applies the mesh's pipeline, if it has one.
+    pub fn apply_pipeline(&self) {
+        if let Some(pipeline) = &self.pipeline {
+            pipeline.apply();
+        }
+    }
+
+    /// Enables primitive restart for the draws that follow if the mesh uses it. It is only disabled
+    /// again if it was left enabled, as the capability needs GL 4.3.
+    pub(crate) fn apply_primitive_restart(&self) {
+        if self.primitive_restart {
+            state::enable(gl::PRIMITIVE_RESTART_FIXED_INDEX);
+        } else if state::is_enabled(gl::PRIMITIVE_RESTART_FIXED_INDEX) {
+            state::disable(gl::PRIMITIVE_RESTART_FIXED_INDEX);
+        }
+    }
+
+    pub fn draw_mode(&self) -> GLenum {
+        self.draw_mode
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.index_buffer.as_ref().map_or(0, IndexBuffer::count)
+    }
+
+    pub fn index_type(&self) -> Option<IndexType> {
+        self.index_buffer.as_ref().map(IndexBuffer::index_type)
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.index_buffer.is_some()
+    }
+
+    /// Replaces all vertex data, reallocating the buffer.
+    pub fn set_vertices<V: Pod>(&mut self, vertices: &[V]) -> Result<(), BufferError> {
+        check_vertices(mem::size_of_val(vertices), self.stride)?;
+        self.vertex_buffer.store_bytes(bytemuck::cast_slice(vertices));
+
+        self.vertex_count = mem::size_of_val(vertices) / self.stride;
+        Ok(())
+    }
+
+    /// Overwrites vertices starting at `first_vertex` without reallocating the buffer.
+    pub fn update_vertices<V: Pod>(&self, first_vertex: usize, vertices: &[V]) -> Result<(), BufferError> {
+        let offset = first_vertex * self.stride;
+        let size = mem::size_of_val(vertices);
+        if offset + size > self.vertex_count * self.stride {
+            let end = first_vertex + size.div_ceil(self.stride);
+            return Err(BufferError::OutOfBounds { start: first_vertex, end, len: self.vertex_count });
+        }
+
+        self.vertex_buffer.store_sub_bytes(offset, bytemuck::cast_slice(vertices));
+        Ok(())
+    }
+
+    /// Overwrites indices starting at `first_index` without reallocating the buffer. Fails if an
+    /// index does not fit in the mesh's index type.
+    pub fn update_indices(&self, first_index: usize, indices: &[u32]) -> Result<(), BufferError> {
+        let index_buffer = self.index_buffer.as_ref().ok_or(BufferError::NotIndexed)?;
+
+        index_buffer.update(first_index, indices)
+    }
+
+    pub fn attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    /// Checks the mesh's attributes, per-instance ones included, against the inputs of `program`.
+    pub fn validate(&self, program: &ShaderProgram) -> Result<(), ShaderError> {
+        program.validate_vertex_attributes(&self.attributes)
+    }
+
+    pub fn bind(&self) {
+        self.vao.bind();
+    }
+
+    pub fn unbind(&self) {
+        self.vao.unbind();
+    }
+
+    fn element_count(&self) -> usize {
+        match &self.index_buffer {
+            Some(index_buffer) => index_buffer.count(),
+            None => self.vertex_count,
+        }
+    }
+
+    pub fn draw(&self) {
+        self.submit(0, self.element_count(), 0, None);
+    }
+
+    /// Draws `count` indices (or vertices, for a mesh without indices) starting at `first`.
+    pub fn draw_range(&self, first: usize, count: usize) -> Result<(), BufferError> {
+        self.draw_range_base_vertex(first, count, 0)
+    }
+
+    /// Like `draw_range`, but adds `base_vertex` to every index before fetching vertices, so several
+    /// meshes packed into one vertex buffer can share the same indices.
+    pub fn draw_range_base_vertex(&self, first: usize, count: usize, base_vertex: GLint) -> Result<(), BufferError> {
+        self.check_range(first, count)?;
+        self.submit(first, count, base_vertex, None);
+        Ok(())
+    }
+
+    /// Draws the whole mesh `instance_count` times in one call.
+    pub fn draw_instanced(&self, instance_count: usize) {
+        self.submit(0, self.element_count(), 0, Some(instance_count));
+    }
+
+    pub fn draw_range_instanced(&self, first: usize, count: usize, instance_count: usize) -> Result<(), BufferError> {
+        self.check_range(first, count)?;
+        self.submit(first, count, 0, Some(instance_count));
+        Ok(())
+    }
+
+    fn check_range(&self, first: usize, count: usize) -> Result<(), BufferError> {
+        let len = self.element_count();
+        if first + count > len {
+            return Err(BufferError::OutOfBounds { start: first, end: first + count, len });
+        }
+        Ok(())
+    }
+
+    fn submit(&self, first: usize, count: usize, base_vertex: GLint, instance_count: Option<usize>) {
+        self.apply_pipeline();
+        self.vao.bind();
+        self.apply_primitive_restart();
+        unsafe {
+            match (&self.index_buffer, instance_count) {
+                (Some(index_buffer), None) => gl::DrawElementsBaseVertex(
+                    self.draw_mode,
+                    count as GLsizei,
+                    index_buffer.index_type().gl_enum(),
+                    index_buffer.offset_of(first) as *const c_void,
+                    base_vertex,
+                ),
+                (Some(index_buffer), Some(instance_count)) => gl::DrawElementsInstancedBaseVertex(
+                    self.draw_mode,
+                    count as GLsizei,
+                    index_buffer.index_type().gl_enum(),
+                    index_buffer.offset_of(first) as *const c_void,
+                    instance_count as GLsizei,
+                    base_vertex,
+                ),
+                (None, None) => gl::DrawArrays(self.draw_mode, first as GLint + base_vertex, count as GLsizei),
+                (None, Some(instance_count)) => gl::DrawArraysInstanced(
+                    self.draw_mode,
+                    first as GLint + base_vertex,
+                    count as GLsizei,
+                    instance_count as GLsizei,
+                ),
+            }
+        }
+        self.vao.unbind();
+    }
+}
+
+fn check_vertices(size: usize, stride: usize) -> Result<(), BufferError> {
+    if stride == 0 {
+        return Err(BufferError::ZeroStride);
+    }
+    if !size.is_multiple_of(stride) {
+        return Err(BufferError::PartialVertex { size, stride });
+    }
+    Ok(())
+}
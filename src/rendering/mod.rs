@@ -1,12 +1,23 @@
 use std::ptr;
 
-use gl::types::{GLbitfield, GLenum};
+use gl::types::{GLbitfield, GLenum, GLuint};
+
+use crate::errors::{BufferError, ShaderError};
 
 use crate::rendering::rgl::*;
+use crate::rendering::rgl::index_buffer::Index;
 
+pub mod batch;
+pub mod context;
+pub mod instancing;
+pub mod mesh;
+pub mod pipeline;
 pub mod window;
 pub mod rgl;
+pub mod state;
 pub mod texture;
+pub mod tracker;
+pub mod vertex;
 
 pub struct Renderer {
     vao: Vao,
@@ -25,14 +36,27 @@ impl Renderer {
         Renderer { vao, vertex_buffer, index_buffer, attributes }
     }
 
+    /// Points `attribute` at the renderer's vertex buffer and keeps it for `apply_attributes`.
     pub fn with_attribute(&mut self, attribute: VertexAttribute) {
+        attribute.apply_to(&self.vao, &self.vertex_buffer);
         self.attributes.push(attribute);
     }
 
+    /// Disables and forgets every attribute; the layout can be rebuilt with `with_attribute`.
     pub fn remove_attributes(&mut self) {
+        self.vao.bind();
+        self.disable_attributes();
+        self.vao.unbind();
         self.attributes.clear();
     }
 
+    /// Re-applies the stored attributes, e.g. after another layout was used with this VAO.
+    pub fn apply_attributes(&self) {
+        for attribute in &self.attributes {
+            attribute.apply_to(&self.vao, &self.vertex_buffer);
+        }
+    }
+
     pub fn enable_attributes(&self) {
         for attribute in &self.attributes {
             attribute.enable();
@@ -45,6 +69,10 @@ impl Renderer {
         }
     }
 
+    pub fn validate(&self, program: &ShaderProgram) -> Result<(), ShaderError> {
+        program.validate_vertex_attributes(&self.attributes)
+    }
+
     pub fn bind(&self) {
         self.vao.bind();
         self.vertex_buffer.bind();
@@ -63,10 +91,31 @@ impl Renderer {
         }
     }
 
+    /// Clears the buffers in `mask`. Write masks left by the last applied pipeline are lifted while
+    /// clearing, so e.g. a read-only depth state does not stop the depth buffer from being cleared.
     pub fn clear(mask: GLbitfield) {
+        let Some(pipeline) = state::pipeline() else {
+            // Nothing is known about the masks, so open them; the next pipeline re-applies all of its state.
+            unsafe {
+                if mask & gl::COLOR_BUFFER_BIT != 0 {
+                    gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                }
+                if mask & gl::DEPTH_BUFFER_BIT != 0 {
+                    gl::DepthMask(gl::TRUE);
+                }
+                if mask & gl::STENCIL_BUFFER_BIT != 0 {
+                    gl::StencilMask(GLuint::MAX);
+                }
+                gl::Clear(mask);
+            }
+            return;
+        };
+
+        pipeline.for_clear(mask).apply();
         unsafe {
             gl::Clear(mask);
         }
+        pipeline.apply();
     }
 
     pub fn draw_arrays(&self, draw_mode: GLenum, vertices: Vec<f32>, count: i32) {
@@ -77,12 +126,12 @@ impl Renderer {
         }
     }
 
-    pub fn draw_elements(&self, draw_mode: GLenum, vertices: Vec<f32>, indices: Vec<i32>, count: i32) {
+    pub fn draw_elements<I: Index>(&self, draw_mode: GLenum, vertices: Vec<f32>, indices: &[I], count: i32) {
         self.vertex_buffer.store_f32(vertices.as_slice());
-        self.index_buffer.store_i32(indices.as_slice());
+        self.index_buffer.store_bytes(bytemuck::cast_slice(indices));
 
         unsafe {
-            gl::DrawElements(draw_mode, count, gl::UNSIGNED_INT, ptr::null())
+            gl::DrawElements(draw_mode, count, I::TYPE.gl_enum(), ptr::null())
         }
     }
 }
@@ -119,7 +168,24 @@ impl <T> VertexFormat<T> {
         }
     }
 
+    pub fn validate(&self, program: &ShaderProgram) -> Result<(), ShaderError> {
+        program.validate_vertex_attributes(&self.attributes)
+    }
+
     pub fn get_attributes_clone(&self) -> Vec<VertexAttribute> {
         self.attributes.clone()
     }
+
+    /// Points the format's attributes at `buffer` in `vao`.
+    pub fn apply_to(&self, vao: &Vao, buffer: &BufferObject) {
+        for attribute in &self.attributes {
+            attribute.apply_to(vao, buffer);
+        }
+    }
+
+    /// Records the format in `vao` against buffer binding point `binding`, so any buffer attached
+    /// there with `Vao::bind_vertex_buffer` is read with this layout.
+    pub fn apply_format(&self, vao: &Vao, binding: GLuint) -> Result<(), BufferError> {
+        vao.apply_format(binding, &self.attributes)
+    }
 }
\ No newline at end of file
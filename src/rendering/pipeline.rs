@@ -0,0 +1,608 @@
+use gl::types::*;
+use crate::rendering::rgl::ToGLboolean;
+use crate::rendering::state;
+
+/// How many color attachments a pipeline describes blending for; GL guarantees at least eight draw buffers.
+pub const MAX_COLOR_ATTACHMENTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            CompareFunc::Never => gl::NEVER,
+            CompareFunc::Less => gl::LESS,
+            CompareFunc::Equal => gl::EQUAL,
+            CompareFunc::LessEqual => gl::LEQUAL,
+            CompareFunc::Greater => gl::GREATER,
+            CompareFunc::NotEqual => gl::NOTEQUAL,
+            CompareFunc::GreaterEqual => gl::GEQUAL,
+            CompareFunc::Always => gl::ALWAYS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            BlendFactor::Zero => gl::ZERO,
+            BlendFactor::One => gl::ONE,
+            BlendFactor::SrcColor => gl::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => gl::DST_COLOR,
+            BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => gl::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            BlendFactor::ConstantColor => gl::CONSTANT_COLOR,
+            BlendFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+            BlendFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+            BlendFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendEquation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            BlendEquation::Add => gl::FUNC_ADD,
+            BlendEquation::Subtract => gl::FUNC_SUBTRACT,
+            BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+            BlendEquation::Min => gl::MIN,
+            BlendEquation::Max => gl::MAX,
+        }
+    }
+}
+
+/// Blending for one color attachment, with separate equations and factors for color and alpha.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    pub color_equation: BlendEquation,
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub alpha_equation: BlendEquation,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl BlendState {
+    /// Adds `src * src_factor` and `dst * dst_factor` for both color and alpha.
+    pub fn new(src: BlendFactor, dst: BlendFactor) -> BlendState {
+        BlendState {
+            color_equation: BlendEquation::Add,
+            src_color: src,
+            dst_color: dst,
+            alpha_equation: BlendEquation::Add,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+
+    /// Classic transparency for colors that are not premultiplied by alpha.
+    pub fn alpha() -> BlendState {
+        BlendState::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    pub fn premultiplied_alpha() -> BlendState {
+        BlendState::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    pub fn additive() -> BlendState {
+        BlendState::new(BlendFactor::One, BlendFactor::One)
+    }
+
+    pub fn with_alpha(mut self, equation: BlendEquation, src: BlendFactor, dst: BlendFactor) -> BlendState {
+        self.alpha_equation = equation;
+        self.src_alpha = src;
+        self.dst_alpha = dst;
+        self
+    }
+
+    pub fn with_color_equation(mut self, equation: BlendEquation) -> BlendState {
+        self.color_equation = equation;
+        self
+    }
+}
+
+/// `write` also decides whether `glClear` can clear the depth buffer; `Renderer::clear` lifts it
+/// for the duration of the clear.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    pub test: bool,
+    pub write: bool,
+    pub func: CompareFunc,
+}
+
+impl DepthState {
+    pub fn enabled(func: CompareFunc) -> DepthState {
+        DepthState { test: true, write: true, func }
+    }
+
+    /// Tests against the depth buffer without writing to it, e.g. for transparent geometry.
+    pub fn read_only(func: CompareFunc) -> DepthState {
+        DepthState { test: true, write: false, func }
+    }
+}
+
+impl Default for DepthState {
+    fn default() -> DepthState {
+        DepthState { test: false, write: true, func: CompareFunc::Less }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilOp {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            StencilOp::Keep => gl::KEEP,
+            StencilOp::Zero => gl::ZERO,
+            StencilOp::Replace => gl::REPLACE,
+            StencilOp::Increment => gl::INCR,
+            StencilOp::IncrementWrap => gl::INCR_WRAP,
+            StencilOp::Decrement => gl::DECR,
+            StencilOp::DecrementWrap => gl::DECR_WRAP,
+            StencilOp::Invert => gl::INVERT,
+        }
+    }
+}
+
+/// The stencil test and update for one face orientation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StencilFace {
+    pub func: CompareFunc,
+    pub reference: GLint,
+    pub read_mask: GLuint,
+    pub write_mask: GLuint,
+    pub stencil_fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl StencilFace {
+    /// Compares against `reference` with full masks and leaves the stencil buffer untouched.
+    pub fn new(func: CompareFunc, reference: GLint) -> StencilFace {
+        StencilFace {
+            func,
+            reference,
+            read_mask: GLuint::MAX,
+            write_mask: GLuint::MAX,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+
+    pub fn with_ops(mut self, stencil_fail: StencilOp, depth_fail: StencilOp, pass: StencilOp) -> StencilFace {
+        self.stencil_fail = stencil_fail;
+        self.depth_fail = depth_fail;
+        self.pass = pass;
+        self
+    }
+
+    pub fn with_masks(mut self, read_mask: GLuint, write_mask: GLuint) -> StencilFace {
+        self.read_mask = read_mask;
+        self.write_mask = write_mask;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StencilState {
+    pub front: StencilFace,
+    pub back: StencilFace,
+}
+
+impl StencilState {
+    /// Uses the same test and update for front and back faces.
+    pub fn new(face: StencilFace) -> StencilState {
+        StencilState { front: face, back: face }
+    }
+
+    pub fn separate(front: StencilFace, back: StencilFace) -> StencilState {
+        StencilState { front, back }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    #[default]
+    None,
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FrontFace {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+impl FrontFace {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            FrontFace::CounterClockwise => gl::CCW,
+            FrontFace::Clockwise => gl::CW,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    Line,
+    Point,
+}
+
+impl PolygonMode {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            PolygonMode::Fill => gl::FILL,
+            PolygonMode::Line => gl::LINE,
+            PolygonMode::Point => gl::POINT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColorMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool,
+}
+
+impl ColorMask {
+    pub const ALL: ColorMask = ColorMask { red: true, green: true, blue: true, alpha: true };
+    pub const NONE: ColorMask = ColorMask { red: false, green: false, blue: false, alpha: false };
+}
+
+impl Default for ColorMask {
+    fn default() -> ColorMask {
+        ColorMask::ALL
+    }
+}
+
+/// A rectangle in window pixels, measured from the bottom-left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScissorRect {
+    pub x: GLint,
+    pub y: GLint,
+    pub width: GLsizei,
+    pub height: GLsizei,
+}
+
+/// The fixed-function state a draw runs with. A pipeline describes all of it, so applying one
+/// leaves no state behind from earlier draws; the default matches GL's initial state. Applying goes
+/// through the state cache and only changes what differs from the last applied pipeline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PipelineState {
+    blend: [Option<BlendState>; MAX_COLOR_ATTACHMENTS],
+    depth: DepthState,
+    stencil: Option<StencilState>,
+    cull_mode: CullMode,
+    front_face: FrontFace,
+    polygon_mode: PolygonMode,
+    color_mask: ColorMask,
+    scissor: Option<ScissorRect>,
+}
+
+impl PipelineState {
+    pub fn new() -> PipelineState {
+        PipelineState::default()
+    }
+
+    /// Blends every color attachment the same way.
+    pub fn with_blend(mut self, blend: BlendState) -> PipelineState {
+        self.blend = [Some(blend); MAX_COLOR_ATTACHMENTS];
+        self
+    }
+
+    /// Sets blending for color attachment `attachment` alone; `None` disables it there.
+    pub fn with_attachment_blend(mut self, attachment: usize, blend: Option<BlendState>) -> PipelineState {
+        assert!(attachment < MAX_COLOR_ATTACHMENTS, "color attachment {} is out of range, pipelines describe {}", attachment, MAX_COLOR_ATTACHMENTS);
+        self.blend[attachment] = blend;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: DepthState) -> PipelineState {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_stencil(mut self, stencil: StencilState) -> PipelineState {
+        self.stencil = Some(stencil);
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: CullMode) -> PipelineState {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: FrontFace) -> PipelineState {
+        self.front_face = front_face;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: PolygonMode) -> PipelineState {
+        self.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_color_mask(mut self, color_mask: ColorMask) -> PipelineState {
+        self.color_mask = color_mask;
+        self
+    }
+
+    pub fn with_scissor(mut self, scissor: ScissorRect) -> PipelineState {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    pub fn blend(&self, attachment: usize) -> Option<BlendState> {
+        self.blend.get(attachment).copied().flatten()
+    }
+
+    pub fn depth(&self) -> DepthState {
+        self.depth
+    }
+
+    pub fn stencil(&self) -> Option<StencilState> {
+        self.stencil
+    }
+
+    pub fn cull_mode(&self) -> CullMode {
+        self.cull_mode
+    }
+
+    pub fn front_face(&self) -> FrontFace {
+        self.front_face
+    }
+
+    pub fn polygon_mode(&self) -> PolygonMode {
+        self.polygon_mode
+    }
+
+    pub fn color_mask(&self) -> ColorMask {
+        self.color_mask
+    }
+
+    pub fn scissor(&self) -> Option<ScissorRect> {
+        self.scissor
+    }
+
+    /// Whether every attachment is blended the same way, so the non-indexed calls suffice.
+    fn uniform_blend(&self) -> bool {
+        self.blend.iter().all(|blend| *blend == self.blend[0])
+    }
+
+    /// The pipeline with the write masks lifted for every buffer in `mask`, so a `glClear` of those
+    /// buffers reaches every bit of them.
+    pub(crate) fn for_clear(&self, mask: GLbitfield) -> PipelineState {
+        let mut pipeline = *self;
+        if mask & gl::COLOR_BUFFER_BIT != 0 {
+            pipeline.color_mask = ColorMask::ALL;
+        }
+        if mask & gl::DEPTH_BUFFER_BIT != 0 {
+            pipeline.depth.write = true;
+        }
+        if let (true, Some(stencil)) = (mask & gl::STENCIL_BUFFER_BIT != 0, &mut pipeline.stencil) {
+            stencil.front.write_mask = GLuint::MAX;
+            stencil.back.write_mask = GLuint::MAX;
+        }
+        pipeline
+    }
+
+    /// The front and back stencil write masks the pipeline leaves in place. Without a stencil state
+    /// every bit is writable, since the mask also applies to `glClear`.
+    fn stencil_write_masks(&self) -> [GLuint; 2] {
+        match self.stencil {
+            Some(stencil) => [stencil.front.write_mask, stencil.back.write_mask],
+            None => [GLuint::MAX; 2],
+        }
+    }
+
+    fn stencil_changed(&self, previous: Option<&PipelineState>) -> bool {
+        previous.is_none_or(|previous| previous.stencil != self.stencil)
+    }
+
+    /// The write masks also apply to `glClear`, which is why `Renderer::clear` lifts them while
+    /// clearing and applies the pipeline again afterwards.
+    pub fn apply(&self) {
+        let previous = state::take_pipeline();
+        let previous = previous.as_ref();
+
+        self.apply_blend(previous);
+        self.apply_depth(previous);
+        self.apply_stencil(previous);
+        self.apply_rasterizer(previous);
+
+        state::store_pipeline(*self);
+    }
+
+    fn apply_blend(&self, previous: Option<&PipelineState>) {
+        let changed = previous.is_none_or(|previous| previous.blend != self.blend);
+
+        if self.uniform_blend() {
+            state::set_enabled(gl::BLEND, self.blend[0].is_some());
+            if let (true, Some(blend)) = (changed, self.blend[0]) {
+                unsafe {
+                    gl::BlendEquationSeparate(blend.color_equation.gl_enum(), blend.alpha_equation.gl_enum());
+                    gl::BlendFuncSeparate(blend.src_color.gl_enum(), blend.dst_color.gl_enum(), blend.src_alpha.gl_enum(), blend.dst_alpha.gl_enum());
+                }
+            }
+            return;
+        }
+
+        for (attachment, blend) in self.blend.iter().enumerate() {
+            let attachment = attachment as GLuint;
+            state::set_enabled_indexed(gl::BLEND, attachment, blend.is_some());
+            if let (true, Some(blend)) = (changed, blend) {
+                unsafe {
+                    gl::BlendEquationSeparatei(attachment, blend.color_equation.gl_enum(), blend.alpha_equation.gl_enum());
+                    gl::BlendFuncSeparatei(attachment, blend.src_color.gl_enum(), blend.dst_color.gl_enum(), blend.src_alpha.gl_enum(), blend.dst_alpha.gl_enum());
+                }
+            }
+        }
+    }
+
+    fn depth_changed(&self, previous: Option<&PipelineState>) -> bool {
+        previous.is_none_or(|previous| previous.depth != self.depth)
+    }
+
+    fn apply_depth(&self, previous: Option<&PipelineState>) {
+        state::set_enabled(gl::DEPTH_TEST, self.depth.test);
+        if !self.depth_changed(previous) {
+            return;
+        }
+
+        unsafe {
+            gl::DepthMask(self.depth.write.to_gl_boolean());
+            gl::DepthFunc(self.depth.func.gl_enum());
+        }
+    }
+
+    fn apply_stencil(&self, previous: Option<&PipelineState>) {
+        state::set_enabled(gl::STENCIL_TEST, self.stencil.is_some());
+        if !self.stencil_changed(previous) {
+            return;
+        }
+        let Some(stencil) = self.stencil else {
+            if previous.is_none_or(|previous| previous.stencil_write_masks() != self.stencil_write_masks()) {
+                unsafe {
+                    gl::StencilMask(GLuint::MAX);
+                }
+            }
+            return;
+        };
+
+        for (face, state) in [(gl::FRONT, stencil.front), (gl::BACK, stencil.back)] {
+            unsafe {
+                gl::StencilFuncSeparate(face, state.func.gl_enum(), state.reference, state.read_mask);
+                gl::StencilOpSeparate(face, state.stencil_fail.gl_enum(), state.depth_fail.gl_enum(), state.pass.gl_enum());
+                gl::StencilMaskSeparate(face, state.write_mask);
+            }
+        }
+    }
+
+    fn apply_rasterizer(&self, previous: Option<&PipelineState>) {
+        let cull_face = match self.cull_mode {
+            CullMode::None => None,
+            CullMode::Front => Some(gl::FRONT),
+            CullMode::Back => Some(gl::BACK),
+            CullMode::FrontAndBack => Some(gl::FRONT_AND_BACK),
+        };
+        state::set_enabled(gl::CULL_FACE, cull_face.is_some());
+        state::set_enabled(gl::SCISSOR_TEST, self.scissor.is_some());
+
+        let differs = |same: fn(&PipelineState, &PipelineState) -> bool| previous.is_none_or(|previous| !same(previous, self));
+        unsafe {
+            if let Some(face) = cull_face {
+                if differs(|a, b| a.cull_mode == b.cull_mode) {
+                    gl::CullFace(face);
+                }
+            }
+            if differs(|a, b| a.front_face == b.front_face) {
+                gl::FrontFace(self.front_face.gl_enum());
+            }
+            if differs(|a, b| a.polygon_mode == b.polygon_mode) {
+                gl::PolygonMode(gl::FRONT_AND_BACK, self.polygon_mode.gl_enum());
+            }
+            if differs(|a, b| a.color_mask == b.color_mask) {
+                let mask = self.color_mask;
+                gl::ColorMask(mask.red.to_gl_boolean(), mask.green.to_gl_boolean(), mask.blue.to_gl_boolean(), mask.alpha.to_gl_boolean());
+            }
+            if let Some(scissor) = self.scissor {
+                if differs(|a, b| a.scissor == b.scissor) {
+                    gl::Scissor(scissor.x, scissor.y, scissor.width, scissor.height);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifts_depth_mask_for_clear() {
+        let read_only = PipelineState::new().with_depth(DepthState::read_only(CompareFunc::Less));
+        assert!(read_only.depth_changed(None));
+        assert!(!read_only.depth_changed(Some(&read_only)));
+
+        let cleared = read_only.for_clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        assert!(cleared.depth().write);
+        assert!(cleared.depth_changed(Some(&read_only)));
+        assert!(read_only.depth_changed(Some(&cleared)));
+
+        assert_eq!(read_only.for_clear(gl::COLOR_BUFFER_BIT), read_only);
+    }
+
+    #[test]
+    fn resets_stencil_mask_without_stencil() {
+        let masked = PipelineState::new()
+            .with_stencil(StencilState::new(StencilFace::new(CompareFunc::Always, 1).with_masks(GLuint::MAX, 0)));
+        let plain = PipelineState::new();
+        assert_eq!(masked.stencil_write_masks(), [0, 0]);
+
+        assert!(plain.stencil_changed(Some(&masked)));
+        assert_eq!(plain.stencil_write_masks(), [GLuint::MAX; 2]);
+        assert_eq!(plain.for_clear(gl::STENCIL_BUFFER_BIT).stencil_write_masks(), [GLuint::MAX; 2]);
+        assert_eq!(masked.for_clear(gl::STENCIL_BUFFER_BIT).stencil_write_masks(), [GLuint::MAX; 2]);
+    }
+}
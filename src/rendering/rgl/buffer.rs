@@ -0,0 +1,233 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
+use bytemuck::Pod;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::logger::warn;
+use crate::rendering::rgl::BufferObject;
+
+/// A buffer of plain-data elements. `len` counts the elements holding data and `capacity` the
+/// elements the GL data store has room for; appending beyond the capacity reallocates on the GPU.
+pub struct Buffer<T: Pod> {
+    buffer: BufferObject,
+    len: usize,
+    capacity: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Buffer<T> {
+    pub fn new(r#type: GLenum, usage: GLenum) -> Buffer<T> {
+        Buffer::with_capacity(r#type, usage, 0)
+    }
+
+    pub fn with_capacity(r#type: GLenum, usage: GLenum, capacity: usize) -> Buffer<T> {
+        let buffer = BufferObject::new(r#type, usage);
+        buffer.allocate(capacity * mem::size_of::<T>());
+
+        Buffer { buffer, len: 0, capacity, _marker: PhantomData }
+    }
+
+    pub fn from_slice(r#type: GLenum, usage: GLenum, data: &[T]) -> Buffer<T> {
+        let buffer = BufferObject::new(r#type, usage);
+        buffer.store_bytes(bytemuck::cast_slice(data));
+
+        Buffer { buffer, len: data.len(), capacity: data.len(), _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.len * mem::size_of::<T>()
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.buffer.id()
+    }
+
+    pub fn buffer(&self) -> &BufferObject {
+        &self.buffer
+    }
+
+    pub fn bind(&self) {
+        self.buffer.bind();
+    }
+
+    pub fn unbind(&self) {
+        self.buffer.unbind();
+    }
+
+    /// Replaces the contents. The data store is reused when `data` fits in the current capacity.
+    pub fn set_data(&mut self, data: &[T]) {
+        if data.len() > self.capacity {
+            self.buffer.store_bytes(bytemuck::cast_slice(data));
+            self.capacity = data.len();
+        } else {
+            self.buffer.store_sub_bytes(0, bytemuck::cast_slice(data));
+        }
+
+        self.len = data.len();
+    }
+
+    /// Overwrites elements starting at `offset`. The written range must lie within `len`.
+    pub fn write(&self, offset: usize, data: &[T]) -> Result<(), BufferError> {
+        self.check_range(offset..offset + data.len())?;
+
+        self.buffer.store_sub_bytes(offset * mem::size_of::<T>(), bytemuck::cast_slice(data));
+        Ok(())
+    }
+
+    /// Appends elements, growing the data store if needed.
+    pub fn extend_from_slice(&mut self, data: &[T]) {
+        self.reserve(data.len());
+
+        self.buffer.store_sub_bytes(self.size_bytes(), bytemuck::cast_slice(data));
+
+        self.len += data.len();
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.extend_from_slice(&[value]);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Makes room for at least `additional` more elements. The capacity at least doubles when the
+    /// data store is reallocated, and the existing contents are copied over on the GPU. The buffer
+    /// keeps its GL name, so VAOs and binding points that refer to it stay valid.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len + additional;
+        if required <= self.capacity {
+            return;
+        }
+
+        let capacity = required.max(self.capacity * 2);
+        if self.len == 0 {
+            self.buffer.allocate(capacity * mem::size_of::<T>());
+        } else {
+            // Re-specifying the data store discards it, so the contents wait in a staging buffer.
+            let staging = BufferObject::new(gl::COPY_READ_BUFFER, gl::STREAM_COPY);
+            staging.allocate(self.size_bytes());
+            self.buffer.copy_to(&staging, 0, 0, self.size_bytes());
+            self.buffer.allocate(capacity * mem::size_of::<T>());
+            staging.copy_to(&self.buffer, 0, 0, self.size_bytes());
+        }
+        self.capacity = capacity;
+    }
+
+    pub fn read(&self) -> Vec<T> {
+        self.read_range(0..self.len).unwrap_or_default()
+    }
+
+    /// Copies elements back from the GPU. This stalls until all commands writing the buffer finish.
+    pub fn read_range(&self, range: Range<usize>) -> Result<Vec<T>, BufferError> {
+        self.check_range(range.clone())?;
+
+        let mut data = vec![T::zeroed(); range.len()];
+        self.buffer.read_sub_bytes(range.start * mem::size_of::<T>(), bytemuck::cast_slice_mut(&mut data));
+        Ok(data)
+    }
+
+    pub fn map(&self, range: Range<usize>) -> Result<MappedBuffer<'_, T>, BufferError> {
+        let data = self.map_raw(range, gl::MAP_READ_BIT)?;
+        Ok(MappedBuffer { buffer: &self.buffer, data })
+    }
+
+    /// Maps `range` for writing. With `invalidate` the previous contents of the range are discarded,
+    /// which lets the driver hand out fresh memory instead of waiting for pending draws.
+    pub fn map_mut(&mut self, range: Range<usize>, invalidate: bool) -> Result<MappedBufferMut<'_, T>, BufferError> {
+        let mut access = gl::MAP_READ_BIT | gl::MAP_WRITE_BIT;
+        if invalidate {
+            access = gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_RANGE_BIT;
+        }
+
+        let data = self.map_raw(range, access)?;
+        Ok(MappedBufferMut { buffer: &self.buffer, data })
+    }
+
+    fn map_raw(&self, range: Range<usize>, access: GLbitfield) -> Result<*mut [T], BufferError> {
+        self.check_range(range.clone())?;
+        if range.is_empty() {
+            return Err(BufferError::EmptyRange);
+        }
+
+        let pointer = self.buffer.map_range(range.start * mem::size_of::<T>(), range.len() * mem::size_of::<T>(), access);
+        if pointer.is_null() {
+            return Err(BufferError::MapFailed(self.buffer.id()));
+        }
+
+        Ok(std::ptr::slice_from_raw_parts_mut(pointer as *mut T, range.len()))
+    }
+
+    fn check_range(&self, range: Range<usize>) -> Result<(), BufferError> {
+        if range.start > range.end || range.end > self.len {
+            return Err(BufferError::OutOfBounds { start: range.start, end: range.end, len: self.len });
+        }
+        Ok(())
+    }
+}
+
+fn unmap(buffer: &BufferObject) {
+    if !buffer.unmap() {
+        warn!("Contents of buffer {} were corrupted while it was mapped", buffer.id());
+    }
+}
+
+/// A read-only view of part of a `Buffer`, unmapped when dropped.
+pub struct MappedBuffer<'a, T: Pod> {
+    buffer: &'a BufferObject,
+    data: *mut [T],
+}
+
+impl<T: Pod> Deref for MappedBuffer<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: Pod> Drop for MappedBuffer<'_, T> {
+    fn drop(&mut self) {
+        unmap(self.buffer);
+    }
+}
+
+/// A writable view of part of a `Buffer`, unmapped when dropped. The buffer is borrowed mutably
+/// so it cannot be drawn from or modified while the mapping is alive.
+pub struct MappedBufferMut<'a, T: Pod> {
+    buffer: &'a BufferObject,
+    data: *mut [T],
+}
+
+impl<T: Pod> Deref for MappedBufferMut<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: Pod> DerefMut for MappedBufferMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T: Pod> Drop for MappedBufferMut<'_, T> {
+    fn drop(&mut self) {
+        unmap(self.buffer);
+    }
+}
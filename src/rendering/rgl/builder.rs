@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::fs;
+use std::path::{Path, PathBuf};
+use gl::types::GLuint;
+use crate::errors::ShaderError;
+use crate::rendering::rgl::{ShaderProgram, ShaderStage};
+use crate::rendering::rgl::preprocessor::{PreprocessedSource, Preprocessor};
+use crate::rendering::rgl::program_cache::ProgramCache;
+
+#[derive(Clone, Debug)]
+pub enum ShaderSource {
+    Inline(String),
+    File(PathBuf),
+}
+
+impl ShaderSource {
+    pub fn load(&self) -> Result<String, ShaderError> {
+        match self {
+            ShaderSource::Inline(source) => Ok(source.clone()),
+            ShaderSource::File(path) => fs::read_to_string(path)
+                .map_err(|source| ShaderError::Io { path: path.clone(), source }),
+        }
+    }
+
+    pub fn preprocess(&self, stage: ShaderStage, preprocessor: &Preprocessor) -> Result<PreprocessedSource, ShaderError> {
+        match self {
+            ShaderSource::Inline(source) => preprocessor.process(&self.name(stage), source, None),
+            ShaderSource::File(path) => preprocessor.process_file(path),
+        }
+    }
+
+    pub fn name(&self, stage: ShaderStage) -> String {
+        match self {
+            ShaderSource::Inline(_) => format!("<{} source>", stage),
+            ShaderSource::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShaderProgramBuilder {
+    stages: Vec<(ShaderStage, ShaderSource)>,
+    preprocessor: Preprocessor,
+    cache: Option<ProgramCache>,
+}
+
+impl ShaderProgramBuilder {
+    pub fn new() -> ShaderProgramBuilder {
+        ShaderProgramBuilder { stages: Vec::new(), preprocessor: Preprocessor::new(), cache: None }
+    }
+
+    pub fn with_stage(mut self, stage: ShaderStage, source: ShaderSource) -> ShaderProgramBuilder {
+        self.stages.retain(|(existing, _)| *existing != stage);
+        self.stages.push((stage, source));
+        self
+    }
+
+    pub fn with_source(self, stage: ShaderStage, source: impl Into<String>) -> ShaderProgramBuilder {
+        self.with_stage(stage, ShaderSource::Inline(source.into()))
+    }
+
+    pub fn with_file(self, stage: ShaderStage, path: impl AsRef<Path>) -> ShaderProgramBuilder {
+        self.with_stage(stage, ShaderSource::File(path.as_ref().to_path_buf()))
+    }
+
+    pub fn with_define(mut self, name: impl Into<String>, value: impl Into<String>) -> ShaderProgramBuilder {
+        self.preprocessor = self.preprocessor.with_define(name, value);
+        self
+    }
+
+    pub fn with_include_dir(mut self, dir: impl AsRef<Path>) -> ShaderProgramBuilder {
+        self.preprocessor = self.preprocessor.with_include_dir(dir);
+        self
+    }
+
+    /// Loads the linked program from `cache` when possible and stores it there after compiling.
+    pub fn with_cache(mut self, cache: ProgramCache) -> ShaderProgramBuilder {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn stages(&self) -> &[(ShaderStage, ShaderSource)] {
+        &self.stages
+    }
+
+    pub fn build(&self) -> Result<ShaderProgram, ShaderError> {
+        let (program_handle, dependencies) = self.compile()?;
+        Ok(ShaderProgram::from_handle(program_handle, self.clone(), dependencies))
+    }
+
+    /// Compiles and links every stage, returning the program handle and every file read to do so.
+    pub(crate) fn compile(&self) -> Result<(GLuint, Vec<PathBuf>), ShaderError> {
+        self.validate_stages()?;
+
+        let processed = self.stages.iter()
+            .map(|(stage, source)| source.preprocess(*stage, &self.preprocessor).map(|processed| (*stage, processed)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut dependencies: Vec<PathBuf> = processed.iter()
+            .flat_map(|(_, processed)| processed.dependencies.iter().cloned())
+            .collect();
+        dependencies.sort();
+        dependencies.dedup();
+
+        let cache = self.cache.as_ref().filter(|_| ProgramCache::is_supported());
+        let cache_key = cache.map(|cache| cache.key(&processed));
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            if let Some(program_handle) = cache.load(key) {
+                return Ok((program_handle, dependencies));
+            }
+        }
+
+        let mut shaders = Vec::with_capacity(processed.len());
+        for (stage, processed) in &processed {
+            match ShaderProgram::compile_shader(*stage, processed) {
+                Ok(shader) => shaders.push(shader),
+                Err(err) => {
+                    for shader in shaders {
+                        unsafe { gl::DeleteShader(shader); }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let program_handle = ShaderProgram::link_program(&shaders, cache.is_some())?;
+
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            cache.store(key, program_handle);
+        }
+
+        Ok((program_handle, dependencies))
+    }
+
+    fn validate_stages(&self) -> Result<(), ShaderError> {
+        let has = |stage: ShaderStage| self.stages.iter().any(|(existing, _)| *existing == stage);
+
+        if self.stages.is_empty() {
+            return Err(ShaderError::InvalidStages("no shader stages were given".to_string()));
+        }
+
+        if has(ShaderStage::Compute) {
+            if self.stages.len() > 1 {
+                return Err(ShaderError::InvalidStages("a compute shader cannot be linked with other stages".to_string()));
+            }
+            return Ok(());
+        }
+
+        if !has(ShaderStage::Vertex) {
+            return Err(ShaderError::InvalidStages("a graphics pipeline requires a vertex shader".to_string()));
+        }
+
+        if has(ShaderStage::TessControl) && !has(ShaderStage::TessEvaluation) {
+            return Err(ShaderError::InvalidStages("a tessellation control shader requires a tessellation evaluation shader".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ShaderVariants {
+    builder: ShaderProgramBuilder,
+    programs: HashMap<BTreeMap<String, String>, ShaderProgram>,
+}
+
+impl ShaderVariants {
+    pub fn new(builder: ShaderProgramBuilder) -> ShaderVariants {
+        ShaderVariants { builder, programs: HashMap::new() }
+    }
+
+    /// Returns the program compiled with `defines` added to the builder's own, compiling it on first use.
+    pub fn get(&mut self, defines: &[(&str, &str)]) -> Result<&mut ShaderProgram, ShaderError> {
+        let key: BTreeMap<String, String> = defines.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        match self.programs.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let builder = entry.key().iter()
+                    .fold(self.builder.clone(), |builder, (name, value)| builder.with_define(name, value));
+                Ok(entry.insert(builder.build()?))
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+}
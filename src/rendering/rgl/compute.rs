@@ -0,0 +1,136 @@
+use std::ffi::CString;
+use gl::types::*;
+use crate::errors::ShaderError;
+use crate::rendering::rgl::{BufferObject, ShaderProgram, ShaderStage};
+use crate::rendering::state;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DispatchIndirectCommand {
+    pub num_groups_x: GLuint,
+    pub num_groups_y: GLuint,
+    pub num_groups_z: GLuint,
+}
+
+pub struct StorageBuffer {
+    buffer: BufferObject,
+    size: usize,
+}
+
+impl StorageBuffer {
+    pub fn new(size: usize, usage: GLenum) -> StorageBuffer {
+        let buffer = BufferObject::new(gl::SHADER_STORAGE_BUFFER, usage);
+        buffer.allocate(size);
+
+        StorageBuffer { buffer, size }
+    }
+
+    pub fn from_bytes(data: &[u8], usage: GLenum) -> StorageBuffer {
+        let buffer = BufferObject::new(gl::SHADER_STORAGE_BUFFER, usage);
+        buffer.store_bytes(data);
+
+        StorageBuffer { buffer, size: data.len() }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn buffer(&self) -> &BufferObject {
+        &self.buffer
+    }
+
+    pub fn bind_base(&self, binding: GLuint) {
+        self.buffer.bind_base(binding);
+    }
+
+    pub fn write_bytes(&self, offset: usize, data: &[u8]) {
+        assert!(offset + data.len() <= self.size, "write of {} bytes at {} overflows storage buffer of {} bytes", data.len(), offset, self.size);
+
+        self.buffer.store_sub_bytes(offset, data);
+    }
+
+    /// Reads back `len` bytes starting at `offset`. Issue a `BUFFER_UPDATE_BARRIER_BIT` barrier
+    /// first if the data was written by a shader.
+    pub fn read_bytes(&self, offset: usize, len: usize) -> Vec<u8> {
+        assert!(offset + len <= self.size, "read of {} bytes at {} overflows storage buffer of {} bytes", len, offset, self.size);
+
+        let mut data = vec![0u8; len];
+        self.buffer.read_sub_bytes(offset, &mut data);
+        data
+    }
+}
+
+impl ShaderProgram {
+    pub fn is_compute(&self) -> bool {
+        self.stages.contains(&ShaderStage::Compute)
+    }
+
+    pub fn work_group_size(&self) -> Result<[GLint; 3], ShaderError> {
+        if !self.is_compute() {
+            return Err(ShaderError::NotComputeProgram);
+        }
+
+        let mut size = [0; 3];
+        unsafe {
+            gl::GetProgramiv(self.program_handle, gl::COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+        }
+        Ok(size)
+    }
+
+    pub fn bind_storage_block(&mut self, block_name: &str, binding: GLuint) -> Result<(), ShaderError> {
+        let c_name = CString::new(block_name)
+            .map_err(|_| ShaderError::StorageBlockNotFound(block_name.to_string()))?;
+        let index = unsafe {
+            gl::GetProgramResourceIndex(self.program_handle, gl::SHADER_STORAGE_BLOCK, c_name.as_ptr())
+        };
+
+        if index == gl::INVALID_INDEX {
+            return Err(ShaderError::StorageBlockNotFound(block_name.to_string()));
+        }
+
+        unsafe {
+            gl::ShaderStorageBlockBinding(self.program_handle, index, binding);
+        }
+        self.storage_bindings.insert(block_name.to_string(), binding);
+        Ok(())
+    }
+
+    pub fn dispatch(&self, groups_x: GLuint, groups_y: GLuint, groups_z: GLuint) -> Result<(), ShaderError> {
+        if !self.is_compute() {
+            return Err(ShaderError::NotComputeProgram);
+        }
+
+        self.bind();
+        unsafe {
+            gl::DispatchCompute(groups_x, groups_y, groups_z);
+        }
+        Ok(())
+    }
+
+    /// Dispatches with the group counts stored as a `DispatchIndirectCommand` at `offset` in `buffer`.
+    pub fn dispatch_indirect(&self, buffer: &BufferObject, offset: usize) -> Result<(), ShaderError> {
+        if !self.is_compute() {
+            return Err(ShaderError::NotComputeProgram);
+        }
+
+        self.bind();
+        state::bind_buffer(gl::DISPATCH_INDIRECT_BUFFER, buffer.id());
+        unsafe {
+            gl::DispatchComputeIndirect(offset as GLintptr);
+        }
+        Ok(())
+    }
+}
+
+pub fn memory_barrier(barriers: GLbitfield) {
+    unsafe {
+        gl::MemoryBarrier(barriers);
+    }
+}
+
+pub fn memory_barrier_by_region(barriers: GLbitfield) {
+    unsafe {
+        gl::MemoryBarrierByRegion(barriers);
+    }
+}
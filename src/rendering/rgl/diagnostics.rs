@@ -0,0 +1,262 @@
+use std::fmt;
+use std::fmt::Write;
+use crate::rendering::rgl::ShaderStage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    pub stage: Option<ShaderStage>,
+    pub severity: Severity,
+    pub file: u32,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShaderSourceFile {
+    pub name: String,
+    pub source: String,
+}
+
+impl ShaderSourceFile {
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> ShaderSourceFile {
+        ShaderSourceFile { name: name.into(), source: source.into() }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompileFailure {
+    pub stage: ShaderStage,
+    pub log: String,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+    pub sources: Vec<ShaderSourceFile>,
+}
+
+impl CompileFailure {
+    pub fn new(stage: ShaderStage, log: String, sources: Vec<ShaderSourceFile>) -> CompileFailure {
+        let diagnostics = parse_info_log(Some(stage), &log);
+        CompileFailure { stage, log, diagnostics, sources }
+    }
+
+    /// Rewrites line numbers reported against the expanded source to their original file and line.
+    pub fn remap_lines(&mut self, origin: impl Fn(u32) -> Option<(u32, u32)>) {
+        for diagnostic in &mut self.diagnostics {
+            if let (0, Some(line)) = (diagnostic.file, diagnostic.line) {
+                if let Some((file, original)) = origin(line) {
+                    diagnostic.file = file;
+                    diagnostic.line = Some(original);
+                }
+            }
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ShaderDiagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Renders every diagnostic followed by the offending source line and `context` lines around it.
+    pub fn render(&self, context: u32) -> String {
+        let mut out = String::new();
+
+        if self.diagnostics.is_empty() {
+            out.push_str(self.log.trim_end());
+            return out;
+        }
+
+        for diagnostic in &self.diagnostics {
+            let file = self.sources.get(diagnostic.file as usize);
+            let name = file.map(|file| file.name.as_str()).unwrap_or("<unknown>");
+
+            match diagnostic.line {
+                Some(line) => {
+                    let _ = writeln!(out, "{} {}: {}:{}: {}", self.stage, diagnostic.severity, name, line, diagnostic.message);
+                    if let Some(file) = file {
+                        render_source_lines(&mut out, &file.source, line, context);
+                    }
+                }
+                None => {
+                    let _ = writeln!(out, "{} {}: {}", self.stage, diagnostic.severity, diagnostic.message);
+                }
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+impl fmt::Display for CompileFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(2))
+    }
+}
+
+#[derive(Debug)]
+pub struct LinkFailure {
+    pub log: String,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl LinkFailure {
+    pub fn new(log: String) -> LinkFailure {
+        let diagnostics = parse_info_log(None, &log);
+        LinkFailure { log, diagnostics }
+    }
+}
+
+impl fmt::Display for LinkFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.log.trim_end())
+    }
+}
+
+fn render_source_lines(out: &mut String, source: &str, line: u32, context: u32) {
+    let first = line.saturating_sub(context).max(1);
+    let last = line.saturating_add(context);
+    let width = last.to_string().len();
+
+    for (number, text) in source.lines().enumerate().map(|(i, text)| (i as u32 + 1, text)) {
+        if number < first {
+            continue;
+        }
+        if number > last {
+            break;
+        }
+
+        let marker = if number == line { '>' } else { ' ' };
+        let _ = writeln!(out, "{marker} {number:>width$} | {text}");
+    }
+}
+
+/// Parses a driver info log into structured diagnostics. Understands the Mesa/Intel
+/// (`0:12(5): error: ...`), NVIDIA (`0(12) : error C0000: ...`) and AMD/Apple
+/// (`ERROR: 0:12: ...`) formats; anything else is kept as a message without a line.
+pub fn parse_info_log(stage: Option<ShaderStage>, log: &str) -> Vec<ShaderDiagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_line(line)
+                .map(|(severity, file, line, message)| ShaderDiagnostic {
+                    stage, severity, file, line: Some(line), message: message.to_string(),
+                })
+                .unwrap_or_else(|| ShaderDiagnostic {
+                    stage,
+                    severity: parse_severity(line).map(|(severity, _)| severity).unwrap_or(Severity::Info),
+                    file: 0,
+                    line: None,
+                    message: line.to_string(),
+                })
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<(Severity, u32, u32, &str)> {
+    if let Some((severity, rest)) = parse_severity(line) {
+        // AMD / Apple: "ERROR: 0:12: message"
+        let (file, rest) = take_number(rest.trim_start())?;
+        let rest = rest.strip_prefix(':')?;
+        let (line, rest) = take_number(rest)?;
+        let message = rest.strip_prefix(':')?.trim();
+        return Some((severity, file, line, message));
+    }
+
+    let (file, rest) = take_number(line)?;
+
+    if let Some(rest) = rest.strip_prefix(':') {
+        // Mesa / Intel: "0:12(5): error: message"
+        let (line, mut rest) = take_number(rest)?;
+        if let Some(column) = rest.strip_prefix('(') {
+            let (_, column_rest) = take_number(column)?;
+            rest = column_rest.strip_prefix(')')?;
+        }
+        let rest = rest.strip_prefix(':')?.trim_start();
+        let (severity, message) = parse_severity(rest).unwrap_or((Severity::Info, rest));
+        return Some((severity, file, line, message.trim()));
+    }
+
+    // NVIDIA: "0(12) : error C1008: message"
+    let rest = rest.strip_prefix('(')?;
+    let (line, rest) = take_number(rest)?;
+    let rest = rest.strip_prefix(')')?.trim_start().strip_prefix(':')?.trim_start();
+    let (severity, rest) = parse_severity(rest).unwrap_or((Severity::Info, rest));
+    let message = match rest.split_once(':') {
+        Some((code, message)) if !code.trim().contains(' ') => message,
+        _ => rest,
+    };
+    Some((severity, file, line, message.trim()))
+}
+
+fn parse_severity(text: &str) -> Option<(Severity, &str)> {
+    let (word, rest) = text.split_once(|c: char| c == ':' || c.is_whitespace())?;
+    let severity = match word.to_ascii_lowercase().as_str() {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        "info" | "note" => Severity::Info,
+        _ => return None,
+    };
+    Some((severity, rest))
+}
+
+fn take_number(text: &str) -> Option<(u32, &str)> {
+    let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_driver_formats() {
+        let log = "0:12(5): error: `foo' undeclared\n\
+                   0(7) : warning C7050: \"bar\" might be used before being initialized\n\
+                   ERROR: 1:3: 'baz' : syntax error\n\
+                   compilation terminated.";
+        let diagnostics = parse_info_log(Some(ShaderStage::Fragment), log);
+
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!((diagnostics[0].severity, diagnostics[0].file, diagnostics[0].line), (Severity::Error, 0, Some(12)));
+        assert_eq!(diagnostics[0].message, "`foo' undeclared");
+        assert_eq!((diagnostics[1].severity, diagnostics[1].file, diagnostics[1].line), (Severity::Warning, 0, Some(7)));
+        assert_eq!(diagnostics[1].message, "\"bar\" might be used before being initialized");
+        assert_eq!((diagnostics[2].severity, diagnostics[2].file, diagnostics[2].line), (Severity::Error, 1, Some(3)));
+        assert_eq!(diagnostics[2].message, "'baz' : syntax error");
+        assert_eq!(diagnostics[3].line, None);
+    }
+
+    #[test]
+    fn renders_source_context() {
+        let source = "#version 330 core\nvoid main() {\n    foo = 1.0;\n}\n";
+        let failure = CompileFailure::new(
+            ShaderStage::Vertex,
+            "0:3(5): error: `foo' undeclared\n".to_string(),
+            vec![ShaderSourceFile::new("shader.vsh", source)],
+        );
+
+        assert_eq!(failure.errors().count(), 1);
+        assert_eq!(
+            failure.render(1),
+            "vertex error: shader.vsh:3: `foo' undeclared\n  2 | void main() {\n> 3 |     foo = 1.0;\n  4 | }"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use gl::types::*;
+use crate::errors::ShaderError;
+use crate::logger::{info, warn};
+use crate::rendering::rgl::ShaderProgram;
+use crate::rendering::rgl::reflection::ProgramReflection;
+use crate::rendering::state;
+use crate::rendering::tracker::{self, ResourceKind};
+
+#[derive(Clone, Debug)]
+pub struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+    pub fn new(path: PathBuf) -> WatchedFile {
+        let modified = Self::modified_time(&path);
+        WatchedFile { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    fn has_changed(&self) -> bool {
+        Self::modified_time(&self.path) != self.modified
+    }
+}
+
+impl ShaderProgram {
+    /// Every file the program was built from, including resolved `#include`s.
+    pub fn watched_files(&self) -> impl Iterator<Item = &Path> {
+        self.watched_files.iter().map(WatchedFile::path)
+    }
+
+    pub fn has_changed_on_disk(&self) -> bool {
+        self.watched_files.iter().any(WatchedFile::has_changed)
+    }
+
+    /// Polls the modification times of the program's source files and recompiles it in place if any
+    /// changed. Returns `Ok(true)` when the program was replaced. When recompilation fails the old
+    /// program stays in use and the error is returned; the same edit is not retried on the next poll.
+    pub fn reload_if_changed(&mut self) -> Result<bool, ShaderError> {
+        if !self.has_changed_on_disk() {
+            return Ok(false);
+        }
+
+        for file in &mut self.watched_files {
+            file.modified = WatchedFile::modified_time(&file.path);
+        }
+
+        self.reload().map(|_| true)
+    }
+
+    /// Recompiles the program from its sources, keeping the current program if that fails. Uniform
+    /// locations, uniform block, storage block and sampler bindings are re-resolved against the new program;
+    /// plain uniform values live in the old program and have to be set again.
+    pub fn reload(&mut self) -> Result<(), ShaderError> {
+        let (program_handle, dependencies) = match self.builder.compile() {
+            Ok(compiled) => compiled,
+            Err(err) => {
+                warn!("Shader reload failed, keeping the previous program:\n{}", err);
+                return Err(err);
+            }
+        };
+
+        let old_handle = self.program_handle;
+        self.reflection = ProgramReflection::query(program_handle);
+        self.program_handle = program_handle;
+
+        self.uniform_ids = self.resolve_uniform_ids();
+        self.watched_files = dependencies.into_iter().map(WatchedFile::new).collect();
+
+        let uniform_block_bindings: HashMap<String, GLuint> = std::mem::take(&mut self.uniform_block_bindings);
+        for (name, binding) in uniform_block_bindings {
+            if self.bind_uniform_block(&name, binding).is_err() {
+                warn!("Uniform block {} is no longer active after reload", name);
+            }
+        }
+
+        let storage_bindings: HashMap<String, GLuint> = std::mem::take(&mut self.storage_bindings);
+        for (name, binding) in storage_bindings {
+            if self.bind_storage_block(&name, binding).is_err() {
+                warn!("Storage block {} is no longer active after reload", name);
+            }
+        }
+
+        self.apply_sampler_units();
+
+        unsafe {
+            gl::DeleteProgram(old_handle);
+        }
+        state::forget(ResourceKind::Program, old_handle);
+        tracker::replace_id(ResourceKind::Program, old_handle, self.program_handle);
+
+        info!("Reloaded shader program {}", self.program_handle);
+        Ok(())
+    }
+
+    fn resolve_uniform_ids(&self) -> HashMap<String, GLint> {
+        let mut uniform_ids = self.reflection.uniform_locations();
+        let created: Vec<&String> = self.uniform_ids.keys().filter(|name| !uniform_ids.contains_key(*name)).collect();
+
+        // Names created by hand (e.g. individual array elements) are looked up again by name.
+        for name in created {
+            let Ok(c_name) = CString::new(name.as_str()) else { continue };
+            let location = unsafe { gl::GetUniformLocation(self.program_handle, c_name.as_ptr()) };
+            if location >= 0 {
+                uniform_ids.insert(name.clone(), location);
+            }
+        }
+
+        uniform_ids
+    }
+}
@@ -0,0 +1,161 @@
+use bytemuck::Pod;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::rendering::rgl::BufferObject;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IndexType {
+    U8,
+    U16,
+    U32,
+}
+
+impl IndexType {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            IndexType::U8 => gl::UNSIGNED_BYTE,
+            IndexType::U16 => gl::UNSIGNED_SHORT,
+            IndexType::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            IndexType::U8 => 1,
+            IndexType::U16 => 2,
+            IndexType::U32 => 4,
+        }
+    }
+
+    /// The index that ends a primitive when primitive restart is enabled, which is the largest value
+    /// the type can hold.
+    pub fn restart_index(&self) -> u32 {
+        match self {
+            IndexType::U8 => u8::MAX as u32,
+            IndexType::U16 => u16::MAX as u32,
+            IndexType::U32 => u32::MAX,
+        }
+    }
+
+    /// The smallest type able to address `vertex_count` vertices while keeping the restart index free.
+    pub fn for_vertex_count(vertex_count: usize) -> IndexType {
+        if vertex_count <= u8::MAX as usize {
+            IndexType::U8
+        } else if vertex_count <= u16::MAX as usize {
+            IndexType::U16
+        } else {
+            IndexType::U32
+        }
+    }
+}
+
+pub trait Index: Pod {
+    const TYPE: IndexType;
+}
+
+impl Index for u8 {
+    const TYPE: IndexType = IndexType::U8;
+}
+
+impl Index for u16 {
+    const TYPE: IndexType = IndexType::U16;
+}
+
+impl Index for u32 {
+    const TYPE: IndexType = IndexType::U32;
+}
+
+/// An element array buffer that remembers the type of the indices stored in it.
+pub struct IndexBuffer {
+    buffer: BufferObject,
+    index_type: IndexType,
+    count: usize,
+}
+
+impl IndexBuffer {
+    pub fn new<I: Index>(indices: &[I], usage: GLenum) -> IndexBuffer {
+        let buffer = BufferObject::new(gl::ELEMENT_ARRAY_BUFFER, usage);
+        buffer.store_bytes(bytemuck::cast_slice(indices));
+
+        IndexBuffer { buffer, index_type: I::TYPE, count: indices.len() }
+    }
+
+    /// Stores `indices` using the smallest type that can address `vertex_count` vertices. Fails if an
+    /// index does not fit in that type.
+    pub fn compact(indices: &[u32], vertex_count: usize, usage: GLenum) -> Result<IndexBuffer, BufferError> {
+        Ok(match IndexType::for_vertex_count(vertex_count) {
+            IndexType::U8 => IndexBuffer::new(&narrow::<u8>(indices)?, usage),
+            IndexType::U16 => IndexBuffer::new(&narrow::<u16>(indices)?, usage),
+            IndexType::U32 => IndexBuffer::new(indices, usage),
+        })
+    }
+
+    pub fn index_type(&self) -> IndexType {
+        self.index_type
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn buffer(&self) -> &BufferObject {
+        &self.buffer
+    }
+
+    pub fn bind(&self) {
+        self.buffer.bind();
+    }
+
+    pub fn unbind(&self) {
+        self.buffer.unbind();
+    }
+
+    /// Overwrites indices starting at `first`, converting them to the buffer's index type. Fails
+    /// without writing anything if the indices run past the end or one does not fit in that type.
+    pub fn update(&self, first: usize, indices: &[u32]) -> Result<(), BufferError> {
+        if first + indices.len() > self.count {
+            return Err(BufferError::OutOfBounds { start: first, end: first + indices.len(), len: self.count });
+        }
+
+        let offset = first * self.index_type.size();
+        match self.index_type {
+            IndexType::U8 => self.buffer.store_sub_bytes(offset, &narrow::<u8>(indices)?),
+            IndexType::U16 => self.buffer.store_sub_bytes(offset, bytemuck::cast_slice(&narrow::<u16>(indices)?)),
+            IndexType::U32 => self.buffer.store_sub_bytes(offset, bytemuck::cast_slice(indices)),
+        }
+        Ok(())
+    }
+
+    /// The byte offset of index `first`, for passing to `glDrawElements*`.
+    pub fn offset_of(&self, first: usize) -> usize {
+        first * self.index_type.size()
+    }
+}
+
+fn narrow<I: Index + TryFrom<u32>>(indices: &[u32]) -> Result<Vec<I>, BufferError> {
+    indices.iter()
+        .map(|index| match I::try_from(*index) {
+            Ok(index) => Ok(index),
+            // Keeps restart markers meaning the same thing after narrowing.
+            Err(_) if *index == u32::MAX => Ok(I::try_from(I::TYPE.restart_index()).ok().unwrap()),
+            Err(_) => Err(BufferError::IndexOutOfRange { index: *index, max: I::TYPE.restart_index() }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_smallest_index_type() {
+        assert_eq!(IndexType::for_vertex_count(36), IndexType::U8);
+        assert_eq!(IndexType::for_vertex_count(255), IndexType::U8);
+        assert_eq!(IndexType::for_vertex_count(256), IndexType::U16);
+        assert_eq!(IndexType::for_vertex_count(70_000), IndexType::U32);
+
+        assert_eq!(narrow::<u8>(&[0, 7, u32::MAX, 254]).unwrap(), vec![0, 7, 255, 254]);
+        assert_eq!(narrow::<u16>(&[300, u32::MAX]).unwrap(), vec![300, 65535]);
+        assert!(matches!(narrow::<u8>(&[0, 300]), Err(BufferError::IndexOutOfRange { index: 300, max: 255 })));
+    }
+}
@@ -0,0 +1,123 @@
+use std::ffi::c_void;
+use std::mem;
+use bytemuck::{Pod, Zeroable};
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::rendering::rgl::buffer::Buffer;
+use crate::rendering::rgl::index_buffer::IndexType;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawArraysIndirectCommand {
+    pub count: GLuint,
+    pub instance_count: GLuint,
+    pub first: GLuint,
+    pub base_instance: GLuint,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawElementsIndirectCommand {
+    pub count: GLuint,
+    pub instance_count: GLuint,
+    pub first_index: GLuint,
+    pub base_vertex: GLint,
+    pub base_instance: GLuint,
+}
+
+/// A draw command layout understood by `glMultiDraw*Indirect`.
+pub trait IndirectCommand: Pod {}
+
+impl IndirectCommand for DrawArraysIndirectCommand {}
+
+impl IndirectCommand for DrawElementsIndirectCommand {}
+
+/// A buffer of draw commands executed in one `glMultiDraw*Indirect` call. The VAO holding the
+/// vertex (and index) data must be bound when drawing.
+pub struct IndirectBuffer<C: IndirectCommand> {
+    buffer: Buffer<C>,
+}
+
+impl<C: IndirectCommand> IndirectBuffer<C> {
+    pub fn new(commands: &[C]) -> IndirectBuffer<C> {
+        IndirectBuffer { buffer: Buffer::from_slice(gl::DRAW_INDIRECT_BUFFER, gl::DYNAMIC_DRAW, commands) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn buffer(&self) -> &Buffer<C> {
+        &self.buffer
+    }
+
+    pub fn set_commands(&mut self, commands: &[C]) {
+        self.buffer.set_data(commands);
+    }
+
+    pub fn update(&self, first: usize, commands: &[C]) -> Result<(), BufferError> {
+        self.buffer.write(first, commands)
+    }
+
+    fn check_range(&self, first: usize, count: usize) -> Result<(), BufferError> {
+        if first + count > self.len() {
+            return Err(BufferError::OutOfBounds { start: first, end: first + count, len: self.len() });
+        }
+        Ok(())
+    }
+}
+
+impl IndirectBuffer<DrawArraysIndirectCommand> {
+    pub fn multi_draw_arrays(&self, draw_mode: GLenum) {
+        self.submit_arrays(draw_mode, 0, self.len());
+    }
+
+    pub fn multi_draw_arrays_range(&self, draw_mode: GLenum, first: usize, count: usize) -> Result<(), BufferError> {
+        self.check_range(first, count)?;
+        self.submit_arrays(draw_mode, first, count);
+        Ok(())
+    }
+
+    fn submit_arrays(&self, draw_mode: GLenum, first: usize, count: usize) {
+        self.buffer.bind();
+        unsafe {
+            gl::MultiDrawArraysIndirect(
+                draw_mode,
+                (first * mem::size_of::<DrawArraysIndirectCommand>()) as *const c_void,
+                count as GLsizei,
+                0,
+            );
+        }
+        self.buffer.unbind();
+    }
+}
+
+impl IndirectBuffer<DrawElementsIndirectCommand> {
+    pub fn multi_draw_elements(&self, draw_mode: GLenum, index_type: IndexType) {
+        self.submit_elements(draw_mode, index_type, 0, self.len());
+    }
+
+    pub fn multi_draw_elements_range(&self, draw_mode: GLenum, index_type: IndexType, first: usize, count: usize) -> Result<(), BufferError> {
+        self.check_range(first, count)?;
+        self.submit_elements(draw_mode, index_type, first, count);
+        Ok(())
+    }
+
+    fn submit_elements(&self, draw_mode: GLenum, index_type: IndexType, first: usize, count: usize) {
+        self.buffer.bind();
+        unsafe {
+            gl::MultiDrawElementsIndirect(
+                draw_mode,
+                index_type.gl_enum(),
+                (first * mem::size_of::<DrawElementsIndirectCommand>()) as *const c_void,
+                count as GLsizei,
+                0,
+            );
+        }
+        self.buffer.unbind();
+    }
+}
@@ -0,0 +1,1166 @@
+use std::collections::HashMap;
+use std::ffi::{c_void, CString};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::{fmt, ptr};
+use gl::types::*;
+use glam::{DMat2, DMat3, DMat4, DVec2, DVec3, DVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+use crate::errors::{BufferError, ShaderError};
+use crate::logger::warn;
+use crate::rendering::context::{self, ContextGuard};
+use crate::rendering::state;
+use crate::rendering::rgl::builder::ShaderProgramBuilder;
+use crate::rendering::rgl::diagnostics::{CompileFailure, LinkFailure};
+use crate::rendering::rgl::hot_reload::WatchedFile;
+use crate::rendering::rgl::preprocessor::PreprocessedSource;
+use crate::rendering::rgl::reflection::{array_element_index, glsl_type_name, is_image_type, is_sampler_2d_type, is_sampler_type, ProgramReflection};
+use crate::rendering::rgl::std140::Std140;
+use crate::rendering::rgl::uniform_buffer::UniformBuffer;
+use crate::rendering::texture::Texture;
+use crate::rendering::tracker::{self, ResourceKind};
+use crate::rendering::vertex::{AttributeKind, VertexAttributeLayout};
+
+pub mod buffer;
+pub mod builder;
+pub mod compute;
+pub mod diagnostics;
+pub mod hot_reload;
+pub mod index_buffer;
+pub mod indirect;
+pub mod preprocessor;
+pub mod program_cache;
+pub mod reflection;
+pub mod std140;
+pub mod stream_buffer;
+pub mod uniform_buffer;
+pub mod vertex_input;
+
+pub struct Vao {
+    id: GLuint,
+    context: ContextGuard,
+}
+
+impl Vao {
+    pub fn new() -> Vao {
+        let mut id = 0;
+        unsafe {
+            if context::supports_dsa() {
+                gl::CreateVertexArrays(1, &mut id);
+            } else {
+                gl::GenVertexArrays(1, &mut id);
+            }
+        }
+        tracker::register(ResourceKind::VertexArray, id, 0);
+
+        Vao { id, context: ContextGuard::current() }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn set_label(&self, label: &str) {
+        tracker::set_label(ResourceKind::VertexArray, self.id, label);
+    }
+
+    pub fn bind(&self) {
+        state::bind_vertex_array(self.id);
+    }
+
+    pub fn unbind(&self) {
+        state::bind_vertex_array(0);
+    }
+
+    /// Attaches `buffer` to binding point `binding`, with vertices `stride` bytes apart starting
+    /// `offset` bytes into the buffer. Binding points are part of separate attribute formats, so this
+    /// fails on contexts without GL 4.3.
+    pub fn bind_vertex_buffer(&self, binding: GLuint, buffer: &BufferObject, offset: usize, stride: GLsizei) -> Result<(), BufferError> {
+        check_vertex_attrib_binding()?;
+
+        if context::supports_dsa() {
+            unsafe {
+                gl::VertexArrayVertexBuffer(self.id, binding, buffer.id(), offset as GLintptr, stride);
+            }
+        } else {
+            self.edit(|| unsafe {
+                gl::BindVertexBuffer(binding, buffer.id(), offset as GLintptr, stride);
+            });
+        }
+        Ok(())
+    }
+
+    /// Makes `buffer` the VAO's element buffer. Whichever VAO was bound before stays bound.
+    pub fn bind_element_buffer(&self, buffer: &BufferObject) {
+        if context::supports_dsa() {
+            unsafe {
+                gl::VertexArrayElementBuffer(self.id, buffer.id());
+            }
+            state::forget_binding(gl::ELEMENT_ARRAY_BUFFER);
+        } else {
+            // The element buffer binding is part of the VAO's state, so the VAO has to be bound to set it.
+            self.edit(|| state::bind_buffer(gl::ELEMENT_ARRAY_BUFFER, buffer.id()));
+        }
+    }
+
+    /// Records `attributes` as a format sourced from `binding`, see `VertexAttribute::apply_format`.
+    pub fn apply_format(&self, binding: GLuint, attributes: &[VertexAttribute]) -> Result<(), BufferError> {
+        for attribute in attributes {
+            attribute.apply_format(self, binding)?;
+        }
+        Ok(())
+    }
+
+    /// The bind-to-edit fallback. The VAO is bound only while `edit` runs, and whichever VAO was
+    /// bound before is bound again afterwards.
+    fn edit<R>(&self, edit: impl FnOnce() -> R) -> R {
+        let previous = state::vertex_array_binding();
+        self.bind();
+        let result = edit();
+        state::bind_vertex_array(previous);
+        result
+    }
+}
+
+impl Drop for Vao {
+    fn drop(&mut self) {
+        if self.context.can_delete(ResourceKind::VertexArray, self.id) {
+            unsafe {
+                gl::DeleteVertexArrays(1, &self.id);
+            }
+            tracker::unregister(ResourceKind::VertexArray, self.id);
+            state::forget(ResourceKind::VertexArray, self.id);
+        } else if self.context.is_destroyed() {
+            tracker::unregister(ResourceKind::VertexArray, self.id);
+        }
+    }
+}
+
+pub struct BufferObject {
+    id: GLuint,
+    r#type: GLenum,
+    usage: GLenum,
+    context: ContextGuard,
+}
+
+impl BufferObject {
+    pub fn new(r#type: GLenum, usage: GLenum) -> BufferObject {
+        let mut id = 0;
+        unsafe {
+            if context::supports_dsa() {
+                gl::CreateBuffers(1, &mut id);
+            } else {
+                gl::GenBuffers(1, &mut id);
+            }
+        }
+        tracker::register(ResourceKind::Buffer, id, 0);
+
+        BufferObject { id, r#type, usage, context: ContextGuard::current() }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        state::bind_buffer(self.r#type, self.id);
+    }
+
+    pub fn bind_base(&self, index: GLuint) {
+        unsafe {
+            gl::BindBufferBase(self.r#type, index, self.id);
+        }
+        state::record_buffer(self.r#type, self.id);
+    }
+
+    pub fn unbind(&self) {
+        state::bind_buffer(self.r#type, 0);
+    }
+
+    pub fn set_label(&self, label: &str) {
+        tracker::set_label(ResourceKind::Buffer, self.id, label);
+    }
+
+    /// Records the size of the buffer's data store with the resource tracker.
+    pub(crate) fn track_size(&self, size: usize) {
+        tracker::set_size(ResourceKind::Buffer, self.id, size);
+    }
+
+    pub fn target(&self) -> GLenum {
+        self.r#type
+    }
+
+    pub fn usage(&self) -> GLenum {
+        self.usage
+    }
+
+    pub fn store_f32(&self, data: &[f32]) {
+        self.store_bytes(bytemuck::cast_slice(data));
+    }
+
+    pub fn store_i32(&self, data: &[i32]) {
+        self.store_bytes(bytemuck::cast_slice(data));
+    }
+
+    /// Allocates `size` bytes of uninitialized storage, discarding the current contents.
+    pub fn allocate(&self, size: usize) {
+        self.track_size(size);
+        self.store_raw(size, ptr::null());
+    }
+
+    pub fn store_bytes(&self, data: &[u8]) {
+        self.track_size(data.len());
+        self.store_raw(data.len(), data.as_ptr() as *const c_void);
+    }
+
+    fn store_raw(&self, size: usize, data: *const c_void) {
+        unsafe {
+            if context::supports_dsa() {
+                gl::NamedBufferData(self.id, size as GLsizeiptr, data, self.usage);
+            } else {
+                self.edit(|target| gl::BufferData(target, size as GLsizeiptr, data, self.usage));
+            }
+        }
+    }
+
+    pub fn store_sub_bytes(&self, offset: usize, data: &[u8]) {
+        unsafe {
+            if context::supports_dsa() {
+                gl::NamedBufferSubData(self.id, offset as GLintptr, data.len() as GLsizeiptr, data.as_ptr() as *const c_void);
+            } else {
+                self.edit(|target| gl::BufferSubData(target, offset as GLintptr, data.len() as GLsizeiptr, data.as_ptr() as *const c_void));
+            }
+        }
+    }
+
+    /// Allocates immutable storage of `size` bytes with the given `glBufferStorage` flags. Fails on
+    /// contexts without GL 4.4.
+    pub fn store_immutable(&self, size: usize, flags: GLbitfield) -> Result<(), BufferError> {
+        if !context::capabilities().buffer_storage {
+            return Err(BufferError::Unsupported("Immutable buffer storage (GL 4.4)"));
+        }
+
+        self.track_size(size);
+        unsafe {
+            if context::supports_dsa() {
+                gl::NamedBufferStorage(self.id, size as GLsizeiptr, ptr::null(), flags);
+            } else {
+                self.edit(|target| gl::BufferStorage(target, size as GLsizeiptr, ptr::null(), flags));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_sub_bytes(&self, offset: usize, data: &mut [u8]) {
+        unsafe {
+            if context::supports_dsa() {
+                gl::GetNamedBufferSubData(self.id, offset as GLintptr, data.len() as GLsizeiptr, data.as_mut_ptr() as *mut c_void);
+            } else {
+                self.edit(|target| gl::GetBufferSubData(target, offset as GLintptr, data.len() as GLsizeiptr, data.as_mut_ptr() as *mut c_void));
+            }
+        }
+    }
+
+    /// Copies `size` bytes on the GPU from `read_offset` in this buffer to `write_offset` in `destination`.
+    pub fn copy_to(&self, destination: &BufferObject, read_offset: usize, write_offset: usize, size: usize) {
+        unsafe {
+            if context::supports_dsa() {
+                gl::CopyNamedBufferSubData(self.id, destination.id, read_offset as GLintptr, write_offset as GLintptr, size as GLsizeiptr);
+            } else {
+                state::bind_buffer(gl::COPY_READ_BUFFER, self.id);
+                state::bind_buffer(gl::COPY_WRITE_BUFFER, destination.id);
+                gl::CopyBufferSubData(gl::COPY_READ_BUFFER, gl::COPY_WRITE_BUFFER, read_offset as GLintptr, write_offset as GLintptr, size as GLsizeiptr);
+            }
+        }
+    }
+
+    /// Maps `size` bytes at `offset`, returning null on failure.
+    pub(crate) fn map_range(&self, offset: usize, size: usize, access: GLbitfield) -> *mut c_void {
+        unsafe {
+            if context::supports_dsa() {
+                gl::MapNamedBufferRange(self.id, offset as GLintptr, size as GLsizeiptr, access)
+            } else {
+                self.edit(|target| gl::MapBufferRange(target, offset as GLintptr, size as GLsizeiptr, access))
+            }
+        }
+    }
+
+    /// Unmaps the buffer, returning false if its contents were lost while mapped.
+    pub(crate) fn unmap(&self) -> bool {
+        unsafe {
+            if context::supports_dsa() {
+                gl::UnmapNamedBuffer(self.id) == gl::TRUE
+            } else {
+                self.edit(|target| gl::UnmapBuffer(target)) == gl::TRUE
+            }
+        }
+    }
+
+    /// The bind-to-edit fallback. Edits go through `COPY_WRITE_BUFFER`, which no draw state reads,
+    /// so neither the buffer's own target nor the bound VAO's element buffer is disturbed. The
+    /// buffer stays bound there so consecutive edits skip the bind.
+    fn edit<R>(&self, edit: impl FnOnce(GLenum) -> R) -> R {
+        state::bind_buffer(gl::COPY_WRITE_BUFFER, self.id);
+        edit(gl::COPY_WRITE_BUFFER)
+    }
+}
+
+impl Drop for BufferObject {
+    fn drop(&mut self) {
+        if self.context.can_delete(ResourceKind::Buffer, self.id) {
+            unsafe {
+                gl::DeleteBuffers(1, &self.id);
+            }
+            tracker::unregister(ResourceKind::Buffer, self.id);
+            state::forget(ResourceKind::Buffer, self.id);
+        } else if self.context.is_destroyed() {
+            tracker::unregister(ResourceKind::Buffer, self.id);
+        }
+    }
+}
+
+/// The description of one vertex attribute. Creating one does not touch GL; it is applied to a VAO
+/// with `apply_to` (bind-to-edit) or `apply_format` (separate attribute format, one format shared by
+/// any number of buffers bound with `Vao::bind_vertex_buffer`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    index: GLuint,
+    size: GLint,
+    r#type: GLenum,
+    kind: AttributeKind,
+    normalized: bool,
+    stride: GLsizei,
+    offset: usize,
+    divisor: GLuint,
+}
+
+impl VertexAttribute {
+    pub fn new(
+        index: u32,
+        size: i32,
+        r#type: GLenum,
+        normalized: bool,
+        stride: GLsizei,
+        offset: i32,
+    ) -> VertexAttribute {
+        VertexAttribute { index, size, r#type, kind: AttributeKind::Float, normalized, stride, offset: offset as usize, divisor: 0 }
+    }
+
+    /// An attribute read as `int`/`uint`/`ivecN`/`uvecN` in the shader, without float conversion.
+    pub fn new_integer(index: u32, size: i32, r#type: GLenum, stride: GLsizei, offset: i32) -> VertexAttribute {
+        VertexAttribute { index, size, r#type, kind: AttributeKind::Integer, normalized: false, stride, offset: offset as usize, divisor: 0 }
+    }
+
+    /// An attribute read as `double`/`dvecN` in the shader.
+    pub fn new_double(index: u32, size: i32, stride: GLsizei, offset: i32) -> VertexAttribute {
+        VertexAttribute { index, size, r#type: gl::DOUBLE, kind: AttributeKind::Double, normalized: false, stride, offset: offset as usize, divisor: 0 }
+    }
+
+    pub fn from_layout(layout: &VertexAttributeLayout, stride: GLsizei) -> VertexAttribute {
+        VertexAttribute {
+            index: layout.location,
+            size: layout.components,
+            r#type: layout.gl_type,
+            kind: layout.kind,
+            normalized: layout.normalized,
+            stride,
+            offset: layout.offset,
+            divisor: 0,
+        }
+    }
+
+    pub fn index(&self) -> GLuint {
+        self.index
+    }
+
+    pub fn size(&self) -> GLint {
+        self.size
+    }
+
+    pub fn gl_type(&self) -> GLenum {
+        self.r#type
+    }
+
+    pub fn kind(&self) -> AttributeKind {
+        self.kind
+    }
+
+    pub fn normalized(&self) -> bool {
+        self.normalized
+    }
+
+    pub fn stride(&self) -> GLsizei {
+        self.stride
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn divisor(&self) -> GLuint {
+        self.divisor
+    }
+
+    /// Advances the attribute once every `divisor` instances instead of once per vertex.
+    pub fn with_divisor(mut self, divisor: GLuint) -> VertexAttribute {
+        self.divisor = divisor;
+        self
+    }
+
+    /// Points the attribute at `buffer` in `vao` and enables it. With direct state access the
+    /// attribute gets a buffer binding point of its own, numbered like its location. Whichever VAO
+    /// was bound before stays bound.
+    pub fn apply_to(&self, vao: &Vao, buffer: &BufferObject) {
+        if context::supports_dsa() {
+            self.set_format(vao, 0);
+            unsafe {
+                gl::VertexArrayAttribBinding(vao.id(), self.index, self.index);
+                gl::VertexArrayVertexBuffer(vao.id(), self.index, buffer.id(), self.offset as GLintptr, self.effective_stride());
+                gl::VertexArrayBindingDivisor(vao.id(), self.index, self.divisor);
+                gl::EnableVertexArrayAttrib(vao.id(), self.index);
+            }
+            return;
+        }
+
+        vao.edit(|| {
+            state::bind_buffer(gl::ARRAY_BUFFER, buffer.id());
+            self.apply();
+            self.enable();
+        });
+    }
+
+    /// Sets the attribute up for the VAO and array buffer that are currently bound.
+    pub fn apply(&self) {
+        let offset = self.offset as *const c_void;
+        unsafe {
+            match self.kind {
+                AttributeKind::Float => gl::VertexAttribPointer(self.index, self.size, self.r#type, self.normalized.to_gl_boolean(), self.stride, offset),
+                AttributeKind::Integer => gl::VertexAttribIPointer(self.index, self.size, self.r#type, self.stride, offset),
+                AttributeKind::Double => gl::VertexAttribLPointer(self.index, self.size, self.r#type, self.stride, offset),
+            }
+            gl::VertexAttribDivisor(self.index, self.divisor);
+        }
+    }
+
+    /// Records the attribute's format in `vao` and sources it from buffer binding point `binding`.
+    /// The stride comes from the binding (see `Vao::bind_vertex_buffer`), and a non-zero divisor is
+    /// applied to the whole binding. Fails on contexts without GL 4.3; `apply_to` works everywhere.
+    pub fn apply_format(&self, vao: &Vao, binding: GLuint) -> Result<(), BufferError> {
+        check_vertex_attrib_binding()?;
+
+        if context::supports_dsa() {
+            self.set_format(vao, self.offset as GLuint);
+            unsafe {
+                gl::VertexArrayAttribBinding(vao.id(), self.index, binding);
+                if self.divisor != 0 {
+                    gl::VertexArrayBindingDivisor(vao.id(), binding, self.divisor);
+                }
+                gl::EnableVertexArrayAttrib(vao.id(), self.index);
+            }
+            return Ok(());
+        }
+
+        vao.edit(|| {
+            self.set_format(vao, self.offset as GLuint);
+            unsafe {
+                gl::VertexAttribBinding(self.index, binding);
+                if self.divisor != 0 {
+                    gl::VertexBindingDivisor(binding, self.divisor);
+                }
+            }
+            self.enable();
+        });
+        Ok(())
+    }
+
+    /// Without direct state access `vao` must already be bound.
+    fn set_format(&self, vao: &Vao, relative_offset: GLuint) {
+        unsafe {
+            if context::supports_dsa() {
+                match self.kind {
+                    AttributeKind::Float => gl::VertexArrayAttribFormat(vao.id(), self.index, self.size, self.r#type, self.normalized.to_gl_boolean(), relative_offset),
+                    AttributeKind::Integer => gl::VertexArrayAttribIFormat(vao.id(), self.index, self.size, self.r#type, relative_offset),
+                    AttributeKind::Double => gl::VertexArrayAttribLFormat(vao.id(), self.index, self.size, self.r#type, relative_offset),
+                }
+            } else {
+                match self.kind {
+                    AttributeKind::Float => gl::VertexAttribFormat(self.index, self.size, self.r#type, self.normalized.to_gl_boolean(), relative_offset),
+                    AttributeKind::Integer => gl::VertexAttribIFormat(self.index, self.size, self.r#type, relative_offset),
+                    AttributeKind::Double => gl::VertexAttribLFormat(self.index, self.size, self.r#type, relative_offset),
+                }
+            }
+        }
+    }
+
+    /// Buffer bindings take the stride literally, while a pointer stride of zero means tightly packed.
+    fn effective_stride(&self) -> GLsizei {
+        if self.stride != 0 {
+            return self.stride;
+        }
+
+        match self.r#type {
+            gl::BYTE | gl::UNSIGNED_BYTE => self.size,
+            gl::SHORT | gl::UNSIGNED_SHORT | gl::HALF_FLOAT => self.size * 2,
+            gl::DOUBLE => self.size * 8,
+            gl::INT_2_10_10_10_REV | gl::UNSIGNED_INT_2_10_10_10_REV | gl::UNSIGNED_INT_10F_11F_11F_REV => 4,
+            _ => self.size * 4,
+        }
+    }
+
+    pub fn enable(&self) {
+        unsafe {
+            gl::EnableVertexAttribArray(self.index);
+        }
+    }
+
+    pub fn disable(&self) {
+        unsafe {
+            gl::DisableVertexAttribArray(self.index);
+        }
+    }
+}
+
+fn check_vertex_attrib_binding() -> Result<(), BufferError> {
+    if !context::capabilities().vertex_attrib_binding {
+        return Err(BufferError::Unsupported("Vertex buffer binding points (GL 4.3)"));
+    }
+    Ok(())
+}
+
+pub trait ToGLboolean {
+    fn to_gl_boolean(&self) -> GLboolean;
+}
+
+impl ToGLboolean for bool {
+    fn to_gl_boolean(&self) -> GLboolean {
+        self.clone() as i32 as GLboolean
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::TessControl => gl::TESS_CONTROL_SHADER,
+            ShaderStage::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            ShaderStage::Compute => gl::COMPUTE_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::TessControl => write!(f, "tessellation control"),
+            ShaderStage::TessEvaluation => write!(f, "tessellation evaluation"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+            ShaderStage::Compute => write!(f, "compute"),
+        }
+    }
+}
+
+pub struct ShaderProgram {
+    program_handle: u32,
+    stages: Vec<ShaderStage>,
+    uniform_ids: HashMap<String, GLint>,
+    reflection: ProgramReflection,
+    uniform_block_bindings: HashMap<String, GLuint>,
+    storage_bindings: HashMap<String, GLuint>,
+    sampler_bindings: HashMap<String, SamplerBinding>,
+    builder: ShaderProgramBuilder,
+    watched_files: Vec<WatchedFile>,
+    context: ContextGuard,
+}
+
+impl ShaderProgram {
+    fn from_handle(program_handle: GLuint, builder: ShaderProgramBuilder, dependencies: Vec<PathBuf>) -> ShaderProgram {
+        let reflection = ProgramReflection::query(program_handle);
+        let uniform_ids = reflection.uniform_locations();
+        let stages = builder.stages().iter().map(|(stage, _)| *stage).collect();
+        let watched_files = dependencies.into_iter().map(WatchedFile::new).collect();
+        tracker::register(ResourceKind::Program, program_handle, 0);
+
+        ShaderProgram {
+            program_handle,
+            stages,
+            uniform_ids,
+            reflection,
+            uniform_block_bindings: HashMap::new(),
+            storage_bindings: HashMap::new(),
+            sampler_bindings: HashMap::new(),
+            builder,
+            watched_files,
+            context: ContextGuard::current(),
+        }
+    }
+
+    pub fn new(vertex_shader_path: &str, fragment_shader_path: &str) -> Result<ShaderProgram, ShaderError> {
+        ShaderProgram::builder()
+            .with_file(ShaderStage::Vertex, vertex_shader_path)
+            .with_file(ShaderStage::Fragment, fragment_shader_path)
+            .build()
+    }
+
+    pub fn builder() -> ShaderProgramBuilder {
+        ShaderProgramBuilder::new()
+    }
+
+    fn compile_shader(stage: ShaderStage, processed: &PreprocessedSource) -> Result<GLuint, ShaderError> {
+        let name = &processed.files[0].name;
+        let c_str = CString::new(processed.source.as_bytes()).map_err(|_| ShaderError::NulInSource { stage })?;
+
+        unsafe {
+            let shader = gl::CreateShader(stage.gl_enum());
+            gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
+            gl::CompileShader(shader);
+
+            let mut success = gl::FALSE as GLint;
+            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+            let log = Self::info_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+
+            if success != gl::TRUE as GLint {
+                gl::DeleteShader(shader);
+                let mut failure = CompileFailure::new(stage, log, processed.files.clone());
+                failure.remap_lines(|line| processed.origin(line));
+                return Err(ShaderError::Compile(failure));
+            }
+
+            if !log.trim().is_empty() {
+                warn!("{} shader {} compiled with warnings:\n{}", stage, name, log.trim_end());
+            }
+
+            Ok(shader)
+        }
+    }
+
+    fn link_program(shaders: &[GLuint], retrievable: bool) -> Result<GLuint, ShaderError> {
+        unsafe {
+            let program_handle = gl::CreateProgram();
+            if retrievable {
+                gl::ProgramParameteri(program_handle, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
+            }
+            for shader in shaders {
+                gl::AttachShader(program_handle, *shader);
+            }
+            gl::LinkProgram(program_handle);
+            for shader in shaders {
+                gl::DetachShader(program_handle, *shader);
+                gl::DeleteShader(*shader);
+            }
+
+            let mut success = gl::FALSE as GLint;
+            gl::GetProgramiv(program_handle, gl::LINK_STATUS, &mut success);
+            let log = Self::info_log(program_handle, gl::GetProgramiv, gl::GetProgramInfoLog);
+
+            if success != gl::TRUE as GLint {
+                gl::DeleteProgram(program_handle);
+                return Err(ShaderError::Link(LinkFailure::new(log)));
+            }
+
+            if !log.trim().is_empty() {
+                warn!("Shader program linked with warnings:\n{}", log.trim_end());
+            }
+
+            Ok(program_handle)
+        }
+    }
+
+    unsafe fn info_log(
+        handle: GLuint,
+        get_iv: unsafe fn(GLuint, GLenum, *mut GLint),
+        get_log: unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar),
+    ) -> String {
+        let mut capacity = 0;
+        get_iv(handle, gl::INFO_LOG_LENGTH, &mut capacity);
+        if capacity <= 0 {
+            return String::new();
+        }
+
+        let mut info_log = vec![0u8; capacity as usize];
+        let mut length = 0;
+        get_log(handle, capacity, &mut length, info_log.as_mut_ptr() as *mut GLchar);
+        info_log.truncate(length.clamp(0, capacity) as usize);
+
+        String::from_utf8_lossy(&info_log).into_owned()
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.program_handle
+    }
+
+    pub fn set_label(&self, label: &str) {
+        tracker::set_label(ResourceKind::Program, self.program_handle, label);
+    }
+
+    pub fn stages(&self) -> &[ShaderStage] {
+        &self.stages
+    }
+
+    /// Makes the program current and binds every texture attached with `bind_texture` to its unit.
+    pub fn bind(&self) {
+        state::use_program(self.program_handle);
+        self.bind_textures();
+    }
+
+    pub fn bind_textures(&self) {
+        for binding in self.sampler_bindings.values() {
+            state::bind_texture_unit(binding.unit, binding.target, binding.texture.id());
+        }
+    }
+
+    /// Attaches `texture` to the sampler uniform `sampler_name` through texture unit `unit`. The
+    /// sampler uniform is pointed at the unit right away and the texture is rebound on every `bind`.
+    /// The program holds on to the texture until it is unbound, so the name it rebinds stays valid.
+    pub fn bind_texture(&mut self, sampler_name: &str, texture: Rc<Texture>, unit: GLuint) -> Result<(), ShaderError> {
+        let location = *self.uniform_ids.get(sampler_name)
+            .ok_or_else(|| ShaderError::UniformNotFound(sampler_name.to_string()))?;
+
+        if let Some(uniform) = self.reflection.uniform(sampler_name) {
+            if !is_sampler_type(uniform.gl_type) {
+                return Err(ShaderError::NotASampler(sampler_name.to_string()));
+            }
+            if !is_sampler_2d_type(uniform.gl_type) {
+                return Err(ShaderError::SamplerTypeMismatch { name: sampler_name.to_string(), found: glsl_type_name(uniform.gl_type) });
+            }
+        }
+
+        state::use_program(self.program_handle);
+        unsafe {
+            gl::Uniform1i(location, unit as GLint);
+        }
+        texture.bind_to_unit(unit);
+
+        self.sampler_bindings.insert(sampler_name.to_string(), SamplerBinding {
+            unit,
+            target: gl::TEXTURE_2D,
+            texture,
+        });
+        Ok(())
+    }
+
+    pub fn unbind_texture(&mut self, sampler_name: &str) {
+        self.sampler_bindings.remove(sampler_name);
+    }
+
+    /// Points every sampler uniform attached with `bind_texture` back at its unit, e.g. after a reload.
+    fn apply_sampler_units(&mut self) {
+        let sampler_bindings = std::mem::take(&mut self.sampler_bindings);
+        for (name, binding) in sampler_bindings {
+            let Some(location) = self.uniform_ids.get(&name) else {
+                warn!("Sampler {} is no longer active", name);
+                continue;
+            };
+            if context::supports_dsa() {
+                unsafe {
+                    gl::ProgramUniform1i(self.program_handle, *location, binding.unit as GLint);
+                }
+            } else {
+                state::use_program(self.program_handle);
+                unsafe {
+                    gl::Uniform1i(*location, binding.unit as GLint);
+                }
+            }
+            self.sampler_bindings.insert(name, binding);
+        }
+    }
+
+    pub fn unbind(&self) {
+        state::use_program(0);
+    }
+
+    pub fn create_uniform(&mut self, uniform_name: &str) -> Result<(), ShaderError> {
+        let c_name = CString::new(uniform_name)
+            .map_err(|_| ShaderError::UniformNotFound(uniform_name.to_string()))?;
+        let uniform_location = unsafe {
+           gl::GetUniformLocation(self.program_handle, c_name.as_ptr())
+        };
+
+        if uniform_location < 0 {
+            Err(ShaderError::UniformNotFound(uniform_name.to_string()))
+        } else {
+            self.uniform_ids.insert(uniform_name.to_string(), uniform_location);
+            Ok(())
+        }
+    }
+
+    /// Makes the program current and sets the uniform on it.
+    pub fn set_uniform(&self, uniform_name: &str, mut value: UniformValue) -> Result<(), ShaderError> {
+        let uniform_location = self.uniform_ids.get(uniform_name)
+            .ok_or_else(|| ShaderError::UniformNotFound(uniform_name.to_string()))?;
+
+        if let Some(uniform) = self.reflection.uniform(uniform_name) {
+            if !value.accepts(uniform.gl_type) {
+                return Err(ShaderError::UniformTypeMismatch {
+                    name: uniform_name.to_string(),
+                    expected: glsl_type_name(uniform.gl_type),
+                    found: value.glsl_type_name(),
+                });
+            }
+
+            let available = (uniform.size.max(1) as usize).saturating_sub(array_element_index(uniform_name));
+            if value.count() > available {
+                return Err(ShaderError::UniformArrayOverflow {
+                    name: uniform_name.to_string(),
+                    available,
+                    given: value.count(),
+                });
+            }
+        }
+
+        state::use_program(self.program_handle);
+        value.call_gl(*uniform_location);
+        Ok(())
+    }
+
+    pub fn has_uniform(&self, uniform_name: &str) -> bool {
+        self.uniform_ids.contains_key(uniform_name)
+    }
+
+    pub fn reflection(&self) -> &ProgramReflection {
+        &self.reflection
+    }
+
+    pub fn bind_uniform_block(&mut self, block_name: &str, binding: GLuint) -> Result<(), ShaderError> {
+        let block = self.reflection.uniform_blocks.iter_mut()
+            .find(|block| block.name == block_name)
+            .ok_or_else(|| ShaderError::UniformBlockNotFound(block_name.to_string()))?;
+
+        unsafe {
+            gl::UniformBlockBinding(self.program_handle, block.index, binding);
+        }
+        block.binding = binding;
+        self.uniform_block_bindings.insert(block_name.to_string(), binding);
+        Ok(())
+    }
+
+    pub fn bind_uniform_buffer<T: Std140>(&mut self, block_name: &str, buffer: &UniformBuffer<T>) -> Result<(), ShaderError> {
+        let data_size = self.reflection.uniform_block(block_name)
+            .ok_or_else(|| ShaderError::UniformBlockNotFound(block_name.to_string()))?
+            .data_size as usize;
+
+        if T::SIZE < data_size {
+            return Err(ShaderError::UniformBlockSizeMismatch { name: block_name.to_string(), expected: data_size, found: T::SIZE });
+        }
+
+        self.bind_uniform_block(block_name, buffer.binding())
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        if self.context.can_delete(ResourceKind::Program, self.program_handle) {
+            unsafe {
+                gl::DeleteProgram(self.program_handle);
+            }
+            tracker::unregister(ResourceKind::Program, self.program_handle);
+            state::forget(ResourceKind::Program, self.program_handle);
+        } else if self.context.is_destroyed() {
+            tracker::unregister(ResourceKind::Program, self.program_handle);
+        }
+    }
+}
+
+struct SamplerBinding {
+    unit: GLuint,
+    target: GLenum,
+    texture: Rc<Texture>,
+}
+
+pub enum UniformValue {
+    Uniform1D { value: [f64; 1] },
+    Uniform1F { value: [f32; 1] },
+    Uniform1I { value: [i32; 1] },
+    Uniform1Ui { value: [u32; 1] },
+    Uniform2D { value: DVec2 },
+    Uniform2F { value: Vec2 },
+    Uniform2I { value: IVec2 },
+    Uniform2Ui { value: UVec2 },
+    Uniform3D { value: DVec3 },
+    Uniform3F { value: Vec3 },
+    Uniform3I { value: IVec3 },
+    Uniform3Ui { value: UVec3 },
+    Uniform4D { value: DVec4 },
+    Uniform4F { value: Vec4 },
+    Uniform4I { value: IVec4 },
+    Uniform4Ui { value: UVec4 },
+    UniformMatrix2D { value: DMat2 },
+    UniformMatrix2F { value: Mat2 },
+    UniformMatrix3D { value: DMat3 },
+    UniformMatrix3F { value: Mat3 },
+    UniformMatrix4D { value: DMat4 },
+    UniformMatrix4F { value: Mat4 },
+    Uniform1DArray { value: Vec<f64> },
+    Uniform1FArray { value: Vec<f32> },
+    Uniform1IArray { value: Vec<i32> },
+    Uniform1UiArray { value: Vec<u32> },
+    Uniform2DArray { value: Vec<DVec2> },
+    Uniform2FArray { value: Vec<Vec2> },
+    Uniform2IArray { value: Vec<IVec2> },
+    Uniform2UiArray { value: Vec<UVec2> },
+    Uniform3DArray { value: Vec<DVec3> },
+    Uniform3FArray { value: Vec<Vec3> },
+    Uniform3IArray { value: Vec<IVec3> },
+    Uniform3UiArray { value: Vec<UVec3> },
+    Uniform4DArray { value: Vec<DVec4> },
+    Uniform4FArray { value: Vec<Vec4> },
+    Uniform4IArray { value: Vec<IVec4> },
+    Uniform4UiArray { value: Vec<UVec4> },
+    UniformMatrix2DArray { value: Vec<DMat2> },
+    UniformMatrix2FArray { value: Vec<Mat2> },
+    UniformMatrix3DArray { value: Vec<DMat3> },
+    UniformMatrix3FArray { value: Vec<Mat3> },
+    UniformMatrix4DArray { value: Vec<DMat4> },
+    UniformMatrix4FArray { value: Vec<Mat4> },
+}
+
+impl UniformValue {
+    pub fn gl_type(&self) -> GLenum {
+        match self {
+            UniformValue::Uniform1D { .. } => gl::DOUBLE,
+            UniformValue::Uniform1F { .. } => gl::FLOAT,
+            UniformValue::Uniform1I { .. } => gl::INT,
+            UniformValue::Uniform1Ui { .. } => gl::UNSIGNED_INT,
+            UniformValue::Uniform2D { .. } => gl::DOUBLE_VEC2,
+            UniformValue::Uniform2F { .. } => gl::FLOAT_VEC2,
+            UniformValue::Uniform2I { .. } => gl::INT_VEC2,
+            UniformValue::Uniform2Ui { .. } => gl::UNSIGNED_INT_VEC2,
+            UniformValue::Uniform3D { .. } => gl::DOUBLE_VEC3,
+            UniformValue::Uniform3F { .. } => gl::FLOAT_VEC3,
+            UniformValue::Uniform3I { .. } => gl::INT_VEC3,
+            UniformValue::Uniform3Ui { .. } => gl::UNSIGNED_INT_VEC3,
+            UniformValue::Uniform4D { .. } => gl::DOUBLE_VEC4,
+            UniformValue::Uniform4F { .. } => gl::FLOAT_VEC4,
+            UniformValue::Uniform4I { .. } => gl::INT_VEC4,
+            UniformValue::Uniform4Ui { .. } => gl::UNSIGNED_INT_VEC4,
+            UniformValue::UniformMatrix2D { .. } => gl::DOUBLE_MAT2,
+            UniformValue::UniformMatrix2F { .. } => gl::FLOAT_MAT2,
+            UniformValue::UniformMatrix3D { .. } => gl::DOUBLE_MAT3,
+            UniformValue::UniformMatrix3F { .. } => gl::FLOAT_MAT3,
+            UniformValue::UniformMatrix4D { .. } => gl::DOUBLE_MAT4,
+            UniformValue::UniformMatrix4F { .. } => gl::FLOAT_MAT4,
+            UniformValue::Uniform1DArray { .. } => gl::DOUBLE,
+            UniformValue::Uniform1FArray { .. } => gl::FLOAT,
+            UniformValue::Uniform1IArray { .. } => gl::INT,
+            UniformValue::Uniform1UiArray { .. } => gl::UNSIGNED_INT,
+            UniformValue::Uniform2DArray { .. } => gl::DOUBLE_VEC2,
+            UniformValue::Uniform2FArray { .. } => gl::FLOAT_VEC2,
+            UniformValue::Uniform2IArray { .. } => gl::INT_VEC2,
+            UniformValue::Uniform2UiArray { .. } => gl::UNSIGNED_INT_VEC2,
+            UniformValue::Uniform3DArray { .. } => gl::DOUBLE_VEC3,
+            UniformValue::Uniform3FArray { .. } => gl::FLOAT_VEC3,
+            UniformValue::Uniform3IArray { .. } => gl::INT_VEC3,
+            UniformValue::Uniform3UiArray { .. } => gl::UNSIGNED_INT_VEC3,
+            UniformValue::Uniform4DArray { .. } => gl::DOUBLE_VEC4,
+            UniformValue::Uniform4FArray { .. } => gl::FLOAT_VEC4,
+            UniformValue::Uniform4IArray { .. } => gl::INT_VEC4,
+            UniformValue::Uniform4UiArray { .. } => gl::UNSIGNED_INT_VEC4,
+            UniformValue::UniformMatrix2DArray { .. } => gl::DOUBLE_MAT2,
+            UniformValue::UniformMatrix2FArray { .. } => gl::FLOAT_MAT2,
+            UniformValue::UniformMatrix3DArray { .. } => gl::DOUBLE_MAT3,
+            UniformValue::UniformMatrix3FArray { .. } => gl::FLOAT_MAT3,
+            UniformValue::UniformMatrix4DArray { .. } => gl::DOUBLE_MAT4,
+            UniformValue::UniformMatrix4FArray { .. } => gl::FLOAT_MAT4,
+        }
+    }
+
+    pub fn glsl_type_name(&self) -> &'static str {
+        glsl_type_name(self.gl_type())
+    }
+
+    /// Number of array elements uploaded by `call_gl`; 1 for the non-array variants.
+    pub fn count(&self) -> usize {
+        match self {
+            UniformValue::Uniform1DArray { value } => value.len(),
+            UniformValue::Uniform1FArray { value } => value.len(),
+            UniformValue::Uniform1IArray { value } => value.len(),
+            UniformValue::Uniform1UiArray { value } => value.len(),
+            UniformValue::Uniform2DArray { value } => value.len(),
+            UniformValue::Uniform2FArray { value } => value.len(),
+            UniformValue::Uniform2IArray { value } => value.len(),
+            UniformValue::Uniform2UiArray { value } => value.len(),
+            UniformValue::Uniform3DArray { value } => value.len(),
+            UniformValue::Uniform3FArray { value } => value.len(),
+            UniformValue::Uniform3IArray { value } => value.len(),
+            UniformValue::Uniform3UiArray { value } => value.len(),
+            UniformValue::Uniform4DArray { value } => value.len(),
+            UniformValue::Uniform4FArray { value } => value.len(),
+            UniformValue::Uniform4IArray { value } => value.len(),
+            UniformValue::Uniform4UiArray { value } => value.len(),
+            UniformValue::UniformMatrix2DArray { value } => value.len(),
+            UniformValue::UniformMatrix2FArray { value } => value.len(),
+            UniformValue::UniformMatrix3DArray { value } => value.len(),
+            UniformValue::UniformMatrix3FArray { value } => value.len(),
+            UniformValue::UniformMatrix4DArray { value } => value.len(),
+            UniformValue::UniformMatrix4FArray { value } => value.len(),
+            _ => 1,
+        }
+    }
+
+    /// Whether this value can be uploaded to a uniform declared with `declared_type`. Besides exact
+    /// matches, booleans accept any scalar component type and samplers/images accept a single int.
+    pub fn accepts(&self, declared_type: GLenum) -> bool {
+        let own_type = self.gl_type();
+        if own_type == declared_type {
+            return true;
+        }
+
+        match declared_type {
+            gl::BOOL => matches!(own_type, gl::INT | gl::UNSIGNED_INT | gl::FLOAT),
+            gl::BOOL_VEC2 => matches!(own_type, gl::INT_VEC2 | gl::UNSIGNED_INT_VEC2 | gl::FLOAT_VEC2),
+            gl::BOOL_VEC3 => matches!(own_type, gl::INT_VEC3 | gl::UNSIGNED_INT_VEC3 | gl::FLOAT_VEC3),
+            gl::BOOL_VEC4 => matches!(own_type, gl::INT_VEC4 | gl::UNSIGNED_INT_VEC4 | gl::FLOAT_VEC4),
+            t if is_sampler_type(t) || is_image_type(t) => own_type == gl::INT,
+            _ => false,
+        }
+    }
+
+    pub fn call_gl(&mut self, location: i32) {
+        match self {
+            UniformValue::Uniform1D { value } => {
+                unsafe { gl::Uniform1dv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform1F { value } => {
+                unsafe { gl::Uniform1fv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform1I { value } => {
+                unsafe { gl::Uniform1iv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform1Ui { value } => {
+                unsafe { gl::Uniform1uiv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform2D { value } => {
+                unsafe { gl::Uniform2dv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform2F { value } => {
+                unsafe { gl::Uniform2fv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform2I { value } => {
+                unsafe { gl::Uniform2iv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform2Ui { value } => {
+                unsafe { gl::Uniform2uiv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform3D { value } => {
+                unsafe { gl::Uniform3dv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform3F { value } => {
+                unsafe { gl::Uniform3fv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform3I { value } => {
+                unsafe { gl::Uniform3iv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform3Ui { value } => {
+                unsafe { gl::Uniform3uiv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform4D { value } => {
+                unsafe { gl::Uniform4dv(location, 1, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform4F { value } => {
+                unsafe { gl::Uniform4fv(location, 1, value.as_mut().as_ptr()) }
+            }
+            UniformValue::Uniform4I { value } => {
+                unsafe { gl::Uniform4iv(location, 1, value.as_mut().as_ptr()) }
+            }
+            UniformValue::Uniform4Ui { value } => {
+                unsafe { gl::Uniform4uiv(location, 1, value.as_mut().as_ptr()) }
+            }
+            UniformValue::UniformMatrix2D { value } => {
+                unsafe { gl::UniformMatrix2dv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::UniformMatrix2F { value } => {
+                unsafe { gl::UniformMatrix2fv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::UniformMatrix3D { value } => {
+                unsafe { gl::UniformMatrix3dv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::UniformMatrix3F { value } => {
+                unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::UniformMatrix4D { value } => {
+                unsafe { gl::UniformMatrix4dv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::UniformMatrix4F { value } => {
+                unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_mut().as_ptr()); }
+            }
+            UniformValue::Uniform1DArray { value } => {
+                unsafe { gl::Uniform1dv(location, value.len() as GLsizei, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::Uniform1FArray { value } => {
+                unsafe { gl::Uniform1fv(location, value.len() as GLsizei, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::Uniform1IArray { value } => {
+                unsafe { gl::Uniform1iv(location, value.len() as GLsizei, value.as_ptr() as *const GLint); }
+            }
+            UniformValue::Uniform1UiArray { value } => {
+                unsafe { gl::Uniform1uiv(location, value.len() as GLsizei, value.as_ptr() as *const GLuint); }
+            }
+            UniformValue::Uniform2DArray { value } => {
+                unsafe { gl::Uniform2dv(location, value.len() as GLsizei, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::Uniform2FArray { value } => {
+                unsafe { gl::Uniform2fv(location, value.len() as GLsizei, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::Uniform2IArray { value } => {
+                unsafe { gl::Uniform2iv(location, value.len() as GLsizei, value.as_ptr() as *const GLint); }
+            }
+            UniformValue::Uniform2UiArray { value } => {
+                unsafe { gl::Uniform2uiv(location, value.len() as GLsizei, value.as_ptr() as *const GLuint); }
+            }
+            UniformValue::Uniform3DArray { value } => {
+                unsafe { gl::Uniform3dv(location, value.len() as GLsizei, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::Uniform3FArray { value } => {
+                unsafe { gl::Uniform3fv(location, value.len() as GLsizei, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::Uniform3IArray { value } => {
+                unsafe { gl::Uniform3iv(location, value.len() as GLsizei, value.as_ptr() as *const GLint); }
+            }
+            UniformValue::Uniform3UiArray { value } => {
+                unsafe { gl::Uniform3uiv(location, value.len() as GLsizei, value.as_ptr() as *const GLuint); }
+            }
+            UniformValue::Uniform4DArray { value } => {
+                unsafe { gl::Uniform4dv(location, value.len() as GLsizei, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::Uniform4FArray { value } => {
+                unsafe { gl::Uniform4fv(location, value.len() as GLsizei, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::Uniform4IArray { value } => {
+                unsafe { gl::Uniform4iv(location, value.len() as GLsizei, value.as_ptr() as *const GLint); }
+            }
+            UniformValue::Uniform4UiArray { value } => {
+                unsafe { gl::Uniform4uiv(location, value.len() as GLsizei, value.as_ptr() as *const GLuint); }
+            }
+            UniformValue::UniformMatrix2DArray { value } => {
+                unsafe { gl::UniformMatrix2dv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::UniformMatrix2FArray { value } => {
+                unsafe { gl::UniformMatrix2fv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::UniformMatrix3DArray { value } => {
+                unsafe { gl::UniformMatrix3dv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::UniformMatrix3FArray { value } => {
+                unsafe { gl::UniformMatrix3fv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLfloat); }
+            }
+            UniformValue::UniformMatrix4DArray { value } => {
+                unsafe { gl::UniformMatrix4dv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLdouble); }
+            }
+            UniformValue::UniformMatrix4FArray { value } => {
+                unsafe { gl::UniformMatrix4fv(location, value.len() as GLsizei, gl::FALSE, value.as_ptr() as *const GLfloat); }
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::errors::ShaderError;
+use crate::rendering::rgl::diagnostics::ShaderSourceFile;
+
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+    defines: BTreeMap<String, String>,
+    include_dirs: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PreprocessedSource {
+    pub source: String,
+    pub files: Vec<ShaderSourceFile>,
+    pub dependencies: Vec<PathBuf>,
+    line_map: Vec<(u32, u32)>,
+}
+
+impl PreprocessedSource {
+    /// Maps a 1-based line of the expanded source back to `(file index, line)` in `files`.
+    pub fn origin(&self, line: u32) -> Option<(u32, u32)> {
+        line.checked_sub(1).and_then(|index| self.line_map.get(index as usize)).copied()
+    }
+}
+
+struct Expansion<'a> {
+    output: PreprocessedSource,
+    stack: Vec<PathBuf>,
+    once: HashSet<PathBuf>,
+    defines: &'a BTreeMap<String, String>,
+}
+
+impl Expansion<'_> {
+    fn push_line(&mut self, text: &str, file: u32, line: u32) {
+        self.output.source.push_str(text);
+        self.output.source.push('\n');
+        self.output.line_map.push((file, line));
+    }
+}
+
+impl Preprocessor {
+    pub fn new() -> Preprocessor {
+        Preprocessor { defines: BTreeMap::new(), include_dirs: Vec::new() }
+    }
+
+    pub fn with_define(mut self, name: impl Into<String>, value: impl Into<String>) -> Preprocessor {
+        self.defines.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_include_dir(mut self, dir: impl AsRef<Path>) -> Preprocessor {
+        self.include_dirs.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn defines(&self) -> &BTreeMap<String, String> {
+        &self.defines
+    }
+
+    pub fn process_file(&self, path: &Path) -> Result<PreprocessedSource, ShaderError> {
+        let source = fs::read_to_string(path)
+            .map_err(|source| ShaderError::Io { path: path.to_path_buf(), source })?;
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        self.process(&path.display().to_string(), &source, Some(&canonical))
+    }
+
+    /// Expands `#include "..."` directives and injects the configured defines right after `#version`.
+    /// `path` is used to resolve includes relative to the root source and to detect cycles through it.
+    pub fn process(&self, name: &str, source: &str, path: Option<&Path>) -> Result<PreprocessedSource, ShaderError> {
+        let mut expansion = Expansion {
+            output: PreprocessedSource {
+                source: String::with_capacity(source.len()),
+                files: vec![ShaderSourceFile::new(name, source)],
+                dependencies: path.map(|path| vec![path.to_path_buf()]).unwrap_or_default(),
+                line_map: Vec::new(),
+            },
+            stack: path.map(|path| vec![path.to_path_buf()]).unwrap_or_default(),
+            once: HashSet::new(),
+            defines: &self.defines,
+        };
+        if let (Some(path), true) = (path, has_pragma_once(source)) {
+            expansion.once.insert(path.to_path_buf());
+        }
+
+        let version_line = source.lines().position(|line| line.trim_start().starts_with("#version"));
+        if version_line.is_none() {
+            Self::inject_defines(&mut expansion, 1);
+        }
+
+        self.expand(&mut expansion, 0, source, path, version_line)?;
+        Ok(expansion.output)
+    }
+
+    fn inject_defines(expansion: &mut Expansion, line: u32) {
+        for (name, value) in expansion.defines {
+            let define = format!("#define {} {}", name, value);
+            expansion.push_line(define.trim_end(), 0, line);
+        }
+    }
+
+    fn expand(
+        &self,
+        expansion: &mut Expansion,
+        file: u32,
+        source: &str,
+        path: Option<&Path>,
+        version_line: Option<usize>,
+    ) -> Result<(), ShaderError> {
+        let name = expansion.output.files[file as usize].name.clone();
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index as u32 + 1;
+            let directive = text.trim_start();
+
+            if Some(index) == version_line {
+                expansion.push_line(text, file, line);
+                Self::inject_defines(expansion, line);
+                continue;
+            }
+
+            if file != 0 && (directive.starts_with("#version") || directive.starts_with("#pragma once")) {
+                continue;
+            }
+
+            let Some(include) = directive.strip_prefix("#include") else {
+                expansion.push_line(text, file, line);
+                continue;
+            };
+
+            let target = Self::parse_include(include).ok_or_else(|| ShaderError::InvalidInclude {
+                file: name.clone(),
+                line,
+                directive: directive.to_string(),
+            })?;
+            let resolved = self.resolve(target, path).ok_or_else(|| ShaderError::IncludeNotFound {
+                file: name.clone(),
+                line,
+                include: target.to_string(),
+            })?;
+
+            // A file guarded by `#pragma once` may be included again while it is still being expanded.
+            if expansion.once.contains(&resolved) {
+                continue;
+            }
+            if expansion.stack.contains(&resolved) {
+                let mut cycle = expansion.stack.clone();
+                cycle.push(resolved);
+                return Err(ShaderError::IncludeCycle(cycle));
+            }
+
+            let included = fs::read_to_string(&resolved)
+                .map_err(|source| ShaderError::Io { path: resolved.clone(), source })?;
+            if has_pragma_once(&included) {
+                expansion.once.insert(resolved.clone());
+            }
+
+            let included_file = expansion.output.files.len() as u32;
+            expansion.output.files.push(ShaderSourceFile::new(resolved.display().to_string(), included.clone()));
+            if !expansion.output.dependencies.contains(&resolved) {
+                expansion.output.dependencies.push(resolved.clone());
+            }
+
+            expansion.stack.push(resolved.clone());
+            self.expand(expansion, included_file, &included, Some(&resolved), None)?;
+            expansion.stack.pop();
+        }
+
+        Ok(())
+    }
+
+    fn parse_include(rest: &str) -> Option<&str> {
+        let rest = rest.trim();
+        let (open, close) = match rest.chars().next()? {
+            '"' => ('"', '"'),
+            '<' => ('<', '>'),
+            _ => return None,
+        };
+        let inner = rest.strip_prefix(open)?;
+        let end = inner.find(close)?;
+        let trailing = inner[end + 1..].trim();
+
+        if end == 0 || !(trailing.is_empty() || trailing.starts_with("//")) {
+            return None;
+        }
+        Some(&inner[..end])
+    }
+
+    fn resolve(&self, target: &str, including: Option<&Path>) -> Option<PathBuf> {
+        let relative = including.and_then(Path::parent).map(|dir| dir.join(target));
+
+        relative.into_iter()
+            .chain(self.include_dirs.iter().map(|dir| dir.join(target)))
+            .chain(std::iter::once(PathBuf::from(target)))
+            .find(|candidate| candidate.is_file())
+            .map(|found| found.canonicalize().unwrap_or(found))
+    }
+}
+
+fn has_pragma_once(source: &str) -> bool {
+    source.lines().any(|line| line.trim_start().starts_with("#pragma once"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("renderia-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn expands_includes_and_defines() {
+        let dir = temp_dir("preprocess");
+        fs::write(dir.join("common.glsl"), "#pragma once\nfloat twice(float x) { return x * 2.0; }\n").unwrap();
+        fs::write(dir.join("main.fsh"), "#version 330 core\n#include \"common.glsl\"\n#include \"common.glsl\"\nvoid main() {}\n").unwrap();
+
+        let processed = Preprocessor::new()
+            .with_define("USE_FOG", "1")
+            .process_file(&dir.join("main.fsh"))
+            .unwrap();
+
+        assert_eq!(
+            processed.source,
+            "#version 330 core\n#define USE_FOG 1\nfloat twice(float x) { return x * 2.0; }\nvoid main() {}\n"
+        );
+        assert_eq!(processed.files.len(), 2);
+        assert_eq!(processed.dependencies.len(), 2);
+        assert_eq!(processed.origin(2), Some((0, 1)));
+        assert_eq!(processed.origin(3), Some((1, 2)));
+        assert_eq!(processed.origin(4), Some((0, 4)));
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = temp_dir("cycle");
+        fs::write(dir.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+        fs::write(dir.join("b.glsl"), "#include \"a.glsl\"\n").unwrap();
+
+        let result = Preprocessor::new().process_file(&dir.join("a.glsl"));
+        assert!(matches!(result, Err(ShaderError::IncludeCycle(cycle)) if cycle.len() == 3));
+    }
+
+    #[test]
+    fn skips_pragma_once_files_still_being_expanded() {
+        let dir = temp_dir("once-cycle");
+        fs::write(dir.join("a.glsl"), "#pragma once\n#include \"b.glsl\"\nfloat a;\n").unwrap();
+        fs::write(dir.join("b.glsl"), "#include \"a.glsl\"\nfloat b;\n").unwrap();
+        fs::write(dir.join("main.glsl"), "#include \"a.glsl\"\nvoid main() {}\n").unwrap();
+
+        let root = Preprocessor::new().process_file(&dir.join("a.glsl")).unwrap();
+        assert_eq!(root.source, "#pragma once\nfloat b;\nfloat a;\n");
+
+        let nested = Preprocessor::new().process_file(&dir.join("main.glsl")).unwrap();
+        assert_eq!(nested.source, "float b;\nfloat a;\nvoid main() {}\n");
+    }
+}
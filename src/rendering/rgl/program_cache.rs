@@ -0,0 +1,179 @@
+use std::ffi::{c_void, CStr};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use gl::types::*;
+use crate::logger::{debug, warn};
+use crate::rendering::rgl::ShaderStage;
+use crate::rendering::rgl::preprocessor::PreprocessedSource;
+
+/// An on-disk cache of linked program binaries. Entries are keyed by a hash of the preprocessed
+/// sources together with the driver's vendor, renderer and version strings, so a driver update
+/// simply misses the cache instead of feeding stale binaries to `glProgramBinary`.
+#[derive(Clone, Debug)]
+pub struct ProgramCache {
+    dir: PathBuf,
+}
+
+impl ProgramCache {
+    pub fn new(dir: impl AsRef<Path>) -> ProgramCache {
+        ProgramCache { dir: dir.as_ref().to_path_buf() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn is_supported() -> bool {
+        let mut formats = 0;
+        unsafe {
+            gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+        }
+        formats > 0
+    }
+
+    pub fn key(&self, sources: &[(ShaderStage, PreprocessedSource)]) -> u64 {
+        let mut hash = Fnv1a::new();
+        for name in [gl::VENDOR, gl::RENDERER, gl::VERSION] {
+            hash.write(driver_string(name).as_bytes());
+            hash.write(&[0]);
+        }
+        for (stage, source) in sources {
+            hash.write(&stage.gl_enum().to_le_bytes());
+            hash.write(source.source.as_bytes());
+            hash.write(&[0]);
+        }
+        hash.finish()
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin", key))
+    }
+
+    /// Creates a program from a cached binary, or returns `None` when there is no entry or the
+    /// driver rejects it. Rejected entries are removed so they are rebuilt from source.
+    pub fn load(&self, key: u64) -> Option<GLuint> {
+        let path = self.entry_path(key);
+        let data = fs::read(&path).ok()?;
+        if data.len() <= 4 {
+            return None;
+        }
+
+        let format = GLenum::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let binary = &data[4..];
+
+        unsafe {
+            let program_handle = gl::CreateProgram();
+            gl::ProgramBinary(program_handle, format, binary.as_ptr() as *const c_void, binary.len() as GLsizei);
+
+            let mut success = gl::FALSE as GLint;
+            gl::GetProgramiv(program_handle, gl::LINK_STATUS, &mut success);
+            if success != gl::TRUE as GLint {
+                gl::DeleteProgram(program_handle);
+                debug!("Driver rejected cached program binary {}, recompiling", path.display());
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+
+            Some(program_handle)
+        }
+    }
+
+    pub fn store(&self, key: u64, program_handle: GLuint) {
+        if let Err(err) = self.try_store(key, program_handle) {
+            warn!("Failed to write program binary cache entry: {}", err);
+        }
+    }
+
+    fn try_store(&self, key: u64, program_handle: GLuint) -> io::Result<()> {
+        let mut length = 0;
+        unsafe {
+            gl::GetProgramiv(program_handle, gl::PROGRAM_BINARY_LENGTH, &mut length);
+        }
+        if length <= 0 {
+            return Ok(());
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut written = 0;
+        let mut format = 0;
+        unsafe {
+            gl::GetProgramBinary(program_handle, length, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void);
+        }
+        binary.truncate(written.max(0) as usize);
+
+        let mut data = Vec::with_capacity(binary.len() + 4);
+        data.extend_from_slice(&format.to_le_bytes());
+        data.extend_from_slice(&binary);
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(key);
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, data)?;
+        fs::rename(temp, path)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::read_dir(&self.dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry?.path();
+                    if path.extension().is_some_and(|extension| extension == "bin") {
+                        fs::remove_file(path)?;
+                    }
+                }
+                Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn driver_string(name: GLenum) -> String {
+    unsafe {
+        let string = gl::GetString(name);
+        if string.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(string as *const _).to_string_lossy().into_owned()
+    }
+}
+
+// FNV-1a, used instead of `DefaultHasher` because cache keys must stay stable across builds.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hash = Fnv1a::new();
+        hash.write(bytes);
+        hash.finish()
+    }
+
+    #[test]
+    fn matches_fnv1a_test_vectors() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use gl::types::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub name: String,
+    pub gl_type: GLenum,
+    pub size: GLint,
+    pub location: GLint,
+    pub block_index: Option<GLuint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: GLuint,
+    pub data_size: GLint,
+    pub binding: GLuint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub gl_type: GLenum,
+    pub size: GLint,
+    pub location: GLint,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProgramReflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+impl ProgramReflection {
+    pub fn query(program: GLuint) -> ProgramReflection {
+        unsafe {
+            ProgramReflection {
+                uniforms: Self::query_uniforms(program),
+                uniform_blocks: Self::query_uniform_blocks(program),
+                attributes: Self::query_attributes(program),
+            }
+        }
+    }
+
+    /// Looks up a uniform by name, also matching array elements such as `lights[3]` to their array.
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        let base = strip_array_index(name);
+        self.uniforms.iter().find(|uniform| uniform.name == name || uniform.name == base)
+    }
+
+    pub fn uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|block| block.name == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+
+    pub fn attribute_at(&self, location: GLint) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|attribute| attribute.location == location)
+    }
+
+    /// Locations of every uniform outside a block, keyed by both `name` and, for arrays, `name[0]`.
+    pub fn uniform_locations(&self) -> HashMap<String, GLint> {
+        let mut locations = HashMap::new();
+
+        for uniform in self.uniforms.iter().filter(|uniform| uniform.location >= 0) {
+            locations.insert(uniform.name.clone(), uniform.location);
+            if uniform.size > 1 {
+                locations.insert(format!("{}[0]", uniform.name), uniform.location);
+            }
+        }
+
+        locations
+    }
+
+    unsafe fn query_uniforms(program: GLuint) -> Vec<UniformInfo> {
+        let mut count = 0;
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+
+        (0..count.max(0) as GLuint)
+            .map(|index| {
+                let (name, gl_type, size) = active_resource(program, index, max_length, gl::GetActiveUniform);
+
+                let mut block_index = -1;
+                gl::GetActiveUniformsiv(program, 1, &index, gl::UNIFORM_BLOCK_INDEX, &mut block_index);
+
+                let location = match CString::new(name.as_str()) {
+                    Ok(c_name) => gl::GetUniformLocation(program, c_name.as_ptr()),
+                    Err(_) => -1,
+                };
+
+                UniformInfo {
+                    name: strip_array_index(&name).to_string(),
+                    gl_type,
+                    size,
+                    location,
+                    block_index: (block_index >= 0).then_some(block_index as GLuint),
+                }
+            })
+            .collect()
+    }
+
+    unsafe fn query_uniform_blocks(program: GLuint) -> Vec<UniformBlockInfo> {
+        let mut count = 0;
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH, &mut max_length);
+
+        (0..count.max(0) as GLuint)
+            .map(|index| {
+                let mut name = vec![0u8; max_length.max(1) as usize];
+                let mut length = 0;
+                gl::GetActiveUniformBlockName(program, index, max_length, &mut length, name.as_mut_ptr() as *mut GLchar);
+                name.truncate(length.max(0) as usize);
+
+                let mut data_size = 0;
+                let mut binding = 0;
+                gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut data_size);
+                gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+
+                UniformBlockInfo {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    index,
+                    data_size,
+                    binding: binding as GLuint,
+                }
+            })
+            .collect()
+    }
+
+    unsafe fn query_attributes(program: GLuint) -> Vec<AttributeInfo> {
+        let mut count = 0;
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_length);
+
+        (0..count.max(0) as GLuint)
+            .map(|index| active_resource(program, index, max_length, gl::GetActiveAttrib))
+            .filter(|(name, _, _)| !name.starts_with("gl_"))
+            .map(|(name, gl_type, size)| {
+                let location = match CString::new(name.as_str()) {
+                    Ok(c_name) => gl::GetAttribLocation(program, c_name.as_ptr()),
+                    Err(_) => -1,
+                };
+
+                AttributeInfo { name: strip_array_index(&name).to_string(), gl_type, size, location }
+            })
+            .collect()
+    }
+}
+
+type GetActiveResource = unsafe fn(GLuint, GLuint, GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar);
+
+unsafe fn active_resource(program: GLuint, index: GLuint, max_length: GLint, get: GetActiveResource) -> (String, GLenum, GLint) {
+    let mut name = vec![0u8; max_length.max(1) as usize];
+    let mut length = 0;
+    let mut size = 0;
+    let mut gl_type = 0;
+    get(program, index, max_length, &mut length, &mut size, &mut gl_type, name.as_mut_ptr() as *mut GLchar);
+    name.truncate(length.max(0) as usize);
+
+    (String::from_utf8_lossy(&name).into_owned(), gl_type, size)
+}
+
+fn strip_array_index(name: &str) -> &str {
+    match name.strip_suffix(']').and_then(|rest| rest.rsplit_once('[')) {
+        Some((base, index)) if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+/// The element a name such as `lights[3]` refers to, or 0 for a name without an index.
+pub fn array_element_index(name: &str) -> usize {
+    name.strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once('['))
+        .and_then(|(_, index)| index.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn is_sampler_type(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE
+            | gl::SAMPLER_1D_SHADOW | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE_SHADOW
+            | gl::SAMPLER_1D_ARRAY | gl::SAMPLER_2D_ARRAY | gl::SAMPLER_1D_ARRAY_SHADOW | gl::SAMPLER_2D_ARRAY_SHADOW
+            | gl::SAMPLER_2D_MULTISAMPLE | gl::SAMPLER_2D_MULTISAMPLE_ARRAY | gl::SAMPLER_BUFFER
+            | gl::SAMPLER_2D_RECT | gl::SAMPLER_2D_RECT_SHADOW | gl::SAMPLER_CUBE_MAP_ARRAY | gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW
+            | gl::INT_SAMPLER_1D | gl::INT_SAMPLER_2D | gl::INT_SAMPLER_3D | gl::INT_SAMPLER_CUBE
+            | gl::INT_SAMPLER_1D_ARRAY | gl::INT_SAMPLER_2D_ARRAY | gl::INT_SAMPLER_2D_MULTISAMPLE
+            | gl::INT_SAMPLER_2D_MULTISAMPLE_ARRAY | gl::INT_SAMPLER_BUFFER | gl::INT_SAMPLER_2D_RECT
+            | gl::INT_SAMPLER_CUBE_MAP_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_1D | gl::UNSIGNED_INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_3D
+            | gl::UNSIGNED_INT_SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_1D_ARRAY | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_BUFFER | gl::UNSIGNED_INT_SAMPLER_2D_RECT | gl::UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY
+    )
+}
+
+/// Sampler types that read a `TEXTURE_2D` texture.
+pub fn is_sampler_2d_type(gl_type: GLenum) -> bool {
+    matches!(gl_type, gl::SAMPLER_2D | gl::SAMPLER_2D_SHADOW | gl::INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_2D)
+}
+
+pub fn is_image_type(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::IMAGE_1D | gl::IMAGE_2D | gl::IMAGE_3D | gl::IMAGE_2D_RECT | gl::IMAGE_CUBE | gl::IMAGE_BUFFER
+            | gl::IMAGE_1D_ARRAY | gl::IMAGE_2D_ARRAY | gl::IMAGE_CUBE_MAP_ARRAY
+            | gl::IMAGE_2D_MULTISAMPLE | gl::IMAGE_2D_MULTISAMPLE_ARRAY
+            | gl::INT_IMAGE_1D | gl::INT_IMAGE_2D | gl::INT_IMAGE_3D | gl::INT_IMAGE_2D_RECT | gl::INT_IMAGE_CUBE
+            | gl::INT_IMAGE_BUFFER | gl::INT_IMAGE_1D_ARRAY | gl::INT_IMAGE_2D_ARRAY | gl::INT_IMAGE_CUBE_MAP_ARRAY
+            | gl::INT_IMAGE_2D_MULTISAMPLE | gl::INT_IMAGE_2D_MULTISAMPLE_ARRAY
+            | gl::UNSIGNED_INT_IMAGE_1D | gl::UNSIGNED_INT_IMAGE_2D | gl::UNSIGNED_INT_IMAGE_3D
+            | gl::UNSIGNED_INT_IMAGE_2D_RECT | gl::UNSIGNED_INT_IMAGE_CUBE | gl::UNSIGNED_INT_IMAGE_BUFFER
+            | gl::UNSIGNED_INT_IMAGE_1D_ARRAY | gl::UNSIGNED_INT_IMAGE_2D_ARRAY | gl::UNSIGNED_INT_IMAGE_CUBE_MAP_ARRAY
+            | gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE | gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE_ARRAY
+    )
+}
+
+/// Returns the GLSL spelling of a reflected type, for error messages.
+pub fn glsl_type_name(gl_type: GLenum) -> &'static str {
+    match gl_type {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::DOUBLE => "double",
+        gl::DOUBLE_VEC2 => "dvec2",
+        gl::DOUBLE_VEC3 => "dvec3",
+        gl::DOUBLE_VEC4 => "dvec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        gl::DOUBLE_MAT2 => "dmat2",
+        gl::DOUBLE_MAT3 => "dmat3",
+        gl::DOUBLE_MAT4 => "dmat4",
+        gl::DOUBLE_MAT2x3 => "dmat2x3",
+        gl::DOUBLE_MAT2x4 => "dmat2x4",
+        gl::DOUBLE_MAT3x2 => "dmat3x2",
+        gl::DOUBLE_MAT3x4 => "dmat3x4",
+        gl::DOUBLE_MAT4x2 => "dmat4x2",
+        gl::DOUBLE_MAT4x3 => "dmat4x3",
+        t if is_sampler_type(t) => "sampler",
+        t if is_image_type(t) => "image",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_array_indices() {
+        assert_eq!(strip_array_index("lights[0]"), "lights");
+        assert_eq!(strip_array_index("material.layers[12]"), "material.layers");
+        assert_eq!(strip_array_index("model"), "model");
+        assert_eq!(strip_array_index("weights[]"), "weights[]");
+        assert_eq!(strip_array_index("lights[i]"), "lights[i]");
+    }
+}
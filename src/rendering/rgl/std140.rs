@@ -0,0 +1,219 @@
+use glam::{DMat2, DMat3, DMat4, DVec2, DVec3, DVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+pub use renderia_derive::Std140;
+
+/// A type with a known std140 layout. `ALIGN` is the base alignment and `SIZE` the number of
+/// bytes the value occupies, including any trailing padding std140 requires before the next member.
+pub trait Std140 {
+    const ALIGN: usize;
+    const SIZE: usize;
+
+    fn write_std140(&self, out: &mut Std140Writer);
+}
+
+pub const fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+/// Structures are aligned to their largest member, rounded up to the alignment of a vec4.
+pub const fn struct_align(member_aligns: &[usize]) -> usize {
+    let mut align = 16;
+    let mut i = 0;
+    while i < member_aligns.len() {
+        if member_aligns[i] > align {
+            align = member_aligns[i];
+        }
+        i += 1;
+    }
+    align_to(align, 16)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Std140Writer {
+    bytes: Vec<u8>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Std140Writer {
+        Std140Writer { bytes: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn write<T: Std140>(&mut self, value: &T) {
+        self.pad_to(align_to(self.bytes.len(), T::ALIGN));
+        value.write_std140(self);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn pad_to(&mut self, offset: usize) {
+        if offset > self.bytes.len() {
+            self.bytes.resize(offset, 0);
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub fn to_std140_bytes<T: Std140>(value: &T) -> Vec<u8> {
+    let mut writer = Std140Writer::new();
+    writer.write(value);
+    writer.pad_to(T::SIZE);
+    writer.into_bytes()
+}
+
+macro_rules! impl_std140_scalar {
+    ($($ty:ty => $align:expr),* $(,)?) => {
+        $(
+            impl Std140 for $ty {
+                const ALIGN: usize = $align;
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn write_std140(&self, out: &mut Std140Writer) {
+                    out.write_bytes(&self.to_ne_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_scalar!(f32 => 4, i32 => 4, u32 => 4, f64 => 8);
+
+impl Std140 for bool {
+    const ALIGN: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, out: &mut Std140Writer) {
+        out.write_bytes(&(*self as u32).to_ne_bytes());
+    }
+}
+
+macro_rules! impl_std140_vector {
+    ($($ty:ty : $scalar:ty, $components:expr => $align:expr),* $(,)?) => {
+        $(
+            impl Std140 for $ty {
+                const ALIGN: usize = $align;
+                const SIZE: usize = $components * std::mem::size_of::<$scalar>();
+
+                fn write_std140(&self, out: &mut Std140Writer) {
+                    for component in self.to_array() {
+                        out.write_bytes(&component.to_ne_bytes());
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_vector!(
+    Vec2: f32, 2 => 8, Vec3: f32, 3 => 16, Vec4: f32, 4 => 16,
+    IVec2: i32, 2 => 8, IVec3: i32, 3 => 16, IVec4: i32, 4 => 16,
+    UVec2: u32, 2 => 8, UVec3: u32, 3 => 16, UVec4: u32, 4 => 16,
+    DVec2: f64, 2 => 16, DVec3: f64, 3 => 32, DVec4: f64, 4 => 32,
+);
+
+// Matrices are laid out as arrays of column vectors, each padded to the array stride.
+macro_rules! impl_std140_matrix {
+    ($($ty:ty : $column:ty, $columns:expr),* $(,)?) => {
+        $(
+            impl Std140 for $ty {
+                const ALIGN: usize = <[$column; $columns] as Std140>::ALIGN;
+                const SIZE: usize = <[$column; $columns] as Std140>::SIZE;
+
+                fn write_std140(&self, out: &mut Std140Writer) {
+                    let columns: [$column; $columns] = std::array::from_fn(|i| self.col(i));
+                    columns.write_std140(out);
+                }
+            }
+        )*
+    };
+}
+
+impl_std140_matrix!(
+    Mat2: Vec2, 2, Mat3: Vec3, 3, Mat4: Vec4, 4,
+    DMat2: DVec2, 2, DMat3: DVec3, 3, DMat4: DVec4, 4,
+);
+
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGN: usize = align_to(T::ALIGN, 16);
+    const SIZE: usize = align_to(T::SIZE, Self::ALIGN) * N;
+
+    fn write_std140(&self, out: &mut Std140Writer) {
+        let stride = align_to(T::SIZE, Self::ALIGN);
+        for element in self {
+            let start = align_to(out.len(), Self::ALIGN);
+            out.pad_to(start);
+            element.write_std140(out);
+            out.pad_to(start + stride);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Std140)]
+    struct Light {
+        position: Vec3,
+        intensity: f32,
+        color: Vec3,
+    }
+
+    #[derive(Std140)]
+    struct Scene {
+        view: Mat4,
+        ambient: Vec2,
+        normal: Mat3,
+        lights: [Light; 2],
+        weights: [f32; 3],
+        enabled: bool,
+    }
+
+    #[test]
+    fn computes_std140_layout() {
+        assert_eq!((Light::ALIGN, Light::SIZE), (16, 32));
+        assert_eq!((<[f32; 3]>::ALIGN, <[f32; 3]>::SIZE), (16, 48));
+        assert_eq!((Mat3::ALIGN, Mat3::SIZE), (16, 48));
+
+        // view 0..64, ambient 64..72, normal 80..128, lights 128..192, weights 192..240, enabled 240..244
+        assert_eq!(Scene::SIZE, 256);
+
+        let scene = Scene {
+            view: Mat4::IDENTITY,
+            ambient: Vec2::new(0.5, 0.25),
+            normal: Mat3::IDENTITY,
+            lights: [
+                Light { position: Vec3::X, intensity: 2.0, color: Vec3::ONE },
+                Light { position: Vec3::Y, intensity: 3.0, color: Vec3::ZERO },
+            ],
+            weights: [1.0, 2.0, 3.0],
+            enabled: true,
+        };
+        let bytes = to_std140_bytes(&scene);
+        let float_at = |offset: usize| f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(bytes.len(), Scene::SIZE);
+        assert_eq!(float_at(68), 0.25);
+        assert_eq!(float_at(80 + 16 + 4), 1.0);
+        assert_eq!(float_at(128 + 12), 2.0);
+        assert_eq!(float_at(160 + 12), 3.0);
+        assert_eq!(float_at(192 + 32), 3.0);
+        assert_eq!(u32::from_ne_bytes(bytes[240..244].try_into().unwrap()), 1);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{mem, ptr};
+use bytemuck::Pod;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::logger::warn;
+use crate::rendering::context::ContextGuard;
+use crate::rendering::rgl::BufferObject;
+use crate::rendering::state;
+
+const FENCE_TIMEOUT_NS: GLuint64 = 1_000_000_000;
+const REGION_ALIGNMENT: usize = 256;
+
+/// A persistently mapped buffer split into `regions` equally sized parts, one per frame in flight.
+/// Each frame writes into its own region while the GPU still reads the previous ones; a fence per
+/// region makes `begin_frame` wait only if the GPU falls `regions` frames behind.
+pub struct StreamBuffer {
+    buffer: BufferObject,
+    mapping: *mut u8,
+    region_size: usize,
+    fences: Vec<GLsync>,
+    region: usize,
+    offset: usize,
+    alignment: usize,
+    context: ContextGuard,
+}
+
+/// Where an allocation ended up in the buffer. `offset` is in bytes from the start of the whole
+/// buffer, ready to be used as a draw offset or `glBindBufferRange` argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamSlice {
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl StreamBuffer {
+    pub fn new(r#type: GLenum, region_size: usize, regions: usize) -> Result<StreamBuffer, BufferError> {
+        if region_size == 0 || regions == 0 {
+            return Err(BufferError::EmptyStreamBuffer { region_size, regions });
+        }
+
+        // Keeps every region start aligned for any element type and for uniform buffer offsets.
+        let region_size = region_size.next_multiple_of(REGION_ALIGNMENT);
+        let buffer = BufferObject::new(r#type, gl::STREAM_DRAW);
+        let size = region_size * regions;
+        let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+
+        buffer.store_immutable(size, flags)?;
+        let mapping = buffer.map_range(0, size, flags) as *mut u8;
+
+        if mapping.is_null() {
+            return Err(BufferError::MapFailed(buffer.id()));
+        }
+
+        Ok(StreamBuffer {
+            buffer,
+            mapping,
+            region_size,
+            fences: vec![ptr::null(); regions],
+            region: regions - 1,
+            offset: 0,
+            alignment: 4,
+            context: ContextGuard::current(),
+        })
+    }
+
+    /// Triple-buffered, which covers the frames a typical driver queues ahead.
+    pub fn triple_buffered(r#type: GLenum, region_size: usize) -> Result<StreamBuffer, BufferError> {
+        StreamBuffer::new(r#type, region_size, 3)
+    }
+
+    /// Aligns every allocation to `alignment` bytes, e.g. `GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT` when
+    /// slices are bound as uniform buffers.
+    pub fn with_alignment(mut self, alignment: usize) -> StreamBuffer {
+        assert!(alignment.is_power_of_two(), "alignment {} is not a power of two", alignment);
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn buffer(&self) -> &BufferObject {
+        &self.buffer
+    }
+
+    pub fn region_size(&self) -> usize {
+        self.region_size
+    }
+
+    pub fn regions(&self) -> usize {
+        self.fences.len()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.region_size - self.offset.min(self.region_size)
+    }
+
+    /// Moves on to the next region, waiting for the GPU to finish the frame that last used it.
+    pub fn begin_frame(&mut self) {
+        self.region = (self.region + 1) % self.fences.len();
+        self.offset = 0;
+
+        let fence = mem::replace(&mut self.fences[self.region], ptr::null());
+        if fence.is_null() {
+            return;
+        }
+
+        unsafe {
+            let mut result = gl::ClientWaitSync(fence, 0, 0);
+            while result == gl::TIMEOUT_EXPIRED {
+                result = gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, FENCE_TIMEOUT_NS);
+            }
+            if result == gl::WAIT_FAILED {
+                warn!("Waiting on stream buffer {} fence failed", self.buffer.id());
+            }
+            gl::DeleteSync(fence);
+        }
+    }
+
+    /// Marks the current region as in use by every command issued so far. Call once per frame after
+    /// the last draw reading from this frame's slices.
+    pub fn end_frame(&mut self) {
+        let fence = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        let previous = mem::replace(&mut self.fences[self.region], fence);
+        if !previous.is_null() {
+            unsafe {
+                gl::DeleteSync(previous);
+            }
+        }
+    }
+
+    /// Hands out room for `len` elements in this frame's region to be filled in place, or `None`
+    /// when the region is full. The memory is write-combined, so avoid reading from it.
+    pub fn allocate<T: Pod>(&mut self, len: usize) -> Option<(StreamSlice, &mut [T])> {
+        let size = len * mem::size_of::<T>();
+        let alignment = self.alignment.max(mem::align_of::<T>());
+        let start = self.offset.next_multiple_of(alignment);
+        if start + size > self.region_size {
+            return None;
+        }
+
+        self.offset = start + size;
+        let offset = self.region * self.region_size + start;
+        let data = unsafe { std::slice::from_raw_parts_mut(self.mapping.add(offset) as *mut T, len) };
+
+        Some((StreamSlice { offset, size }, data))
+    }
+
+    /// Copies `data` into this frame's region, or returns `None` when the region is full.
+    pub fn write<T: Pod>(&mut self, data: &[T]) -> Option<StreamSlice> {
+        let (slice, target) = self.allocate::<T>(data.len())?;
+        target.copy_from_slice(data);
+        Some(slice)
+    }
+
+    /// Binds `slice` to an indexed binding point such as a uniform or storage buffer binding.
+    pub fn bind_range(&self, index: GLuint, slice: StreamSlice) {
+        unsafe {
+            gl::BindBufferRange(self.buffer.target(), index, self.buffer.id(), slice.offset as GLintptr, slice.size as GLsizeiptr);
+        }
+        state::record_buffer(self.buffer.target(), self.buffer.id());
+    }
+}
+
+impl Drop for StreamBuffer {
+    fn drop(&mut self) {
+        if !self.context.is_current() {
+            return;
+        }
+        for fence in self.fences.drain(..).filter(|fence| !fence.is_null()) {
+            unsafe {
+                gl::DeleteSync(fence);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use gl::types::*;
+use crate::errors::BufferError;
+use crate::rendering::context;
+use crate::rendering::rgl::BufferObject;
+use crate::rendering::rgl::std140::{to_std140_bytes, Std140};
+
+/// The uniform buffer binding points handed out in one context.
+#[derive(Debug, Default)]
+struct BindingAllocator {
+    used: Vec<bool>,
+    limit: Option<GLuint>,
+}
+
+impl BindingAllocator {
+    /// Takes the lowest free binding point below `limit`.
+    fn allocate(&mut self, limit: GLuint) -> Option<GLuint> {
+        let index = self.used.iter().position(|used| !used).unwrap_or(self.used.len());
+        if index as GLuint >= limit {
+            return None;
+        }
+
+        if index == self.used.len() {
+            self.used.push(true);
+        } else {
+            self.used[index] = true;
+        }
+        Some(index as GLuint)
+    }
+
+    fn release(&mut self, binding: GLuint) {
+        if let Some(used) = self.used.get_mut(binding as usize) {
+            *used = false;
+        }
+    }
+}
+
+thread_local! {
+    static UNIFORM_BINDINGS: RefCell<HashMap<Option<u64>, BindingAllocator>> = RefCell::new(HashMap::new());
+}
+
+fn allocate_binding(context_id: Option<u64>) -> Result<GLuint, BufferError> {
+    UNIFORM_BINDINGS.with(|bindings| {
+        let mut bindings = bindings.borrow_mut();
+        let allocator = bindings.entry(context_id).or_default();
+        let limit = *allocator.limit.get_or_insert_with(|| {
+            let mut limit = 0;
+            unsafe {
+                gl::GetIntegerv(gl::MAX_UNIFORM_BUFFER_BINDINGS, &mut limit);
+            }
+            limit.max(0) as GLuint
+        });
+
+        allocator.allocate(limit).ok_or(BufferError::BindingsExhausted { limit })
+    })
+}
+
+fn release_binding(context_id: Option<u64>, binding: GLuint) {
+    UNIFORM_BINDINGS.with(|bindings| {
+        if let Some(allocator) = bindings.borrow_mut().get_mut(&context_id) {
+            allocator.release(binding);
+        }
+    });
+}
+
+/// A uniform buffer holding a single std140 value, bound to its own uniform buffer binding point
+/// for as long as it lives so any number of programs can share it via `ShaderProgram::bind_uniform_buffer`.
+pub struct UniformBuffer<T: Std140> {
+    buffer: BufferObject,
+    binding: GLuint,
+    context_id: Option<u64>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    /// Fails once every binding point of the current context is taken by a live uniform buffer.
+    pub fn new(value: &T) -> Result<UniformBuffer<T>, BufferError> {
+        let context_id = context::current_context();
+        let binding = allocate_binding(context_id)?;
+
+        let buffer = BufferObject::new(gl::UNIFORM_BUFFER, gl::DYNAMIC_DRAW);
+        buffer.store_bytes(&to_std140_bytes(value));
+        buffer.bind_base(binding);
+
+        Ok(UniformBuffer { buffer, binding, context_id, _marker: PhantomData })
+    }
+
+    pub fn update(&self, value: &T) {
+        self.buffer.store_sub_bytes(0, &to_std140_bytes(value));
+    }
+
+    pub fn binding(&self) -> GLuint {
+        self.binding
+    }
+
+    /// Re-attaches the buffer to its binding point, e.g. after other code rebound it.
+    pub fn bind_base(&self) {
+        self.buffer.bind_base(self.binding);
+    }
+
+    pub fn buffer(&self) -> &BufferObject {
+        &self.buffer
+    }
+}
+
+impl<T: Std140> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        release_binding(self.context_id, self.binding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_released_bindings_up_to_the_limit() {
+        let mut allocator = BindingAllocator::default();
+        assert_eq!(allocator.allocate(2), Some(0));
+        assert_eq!(allocator.allocate(2), Some(1));
+        assert_eq!(allocator.allocate(2), None);
+
+        allocator.release(0);
+        assert_eq!(allocator.allocate(2), Some(0));
+    }
+}
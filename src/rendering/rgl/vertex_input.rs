@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use gl::types::*;
+use crate::errors::ShaderError;
+use crate::rendering::rgl::reflection::AttributeInfo;
+use crate::rendering::rgl::{ShaderProgram, VertexAttribute};
+use crate::rendering::vertex::AttributeKind;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VertexInputIssue {
+    /// The shader reads a location no vertex attribute feeds.
+    Missing { name: String, location: GLuint },
+    /// A vertex attribute feeds a location the shader does not read. Inputs the shader declares but
+    /// never uses are optimized out by the linker and show up here too.
+    Unused { location: GLuint },
+    /// The attribute is fetched as a different kind than the shader input, e.g. integers through
+    /// `glVertexAttribPointer` into an `ivec4`.
+    KindMismatch { name: String, location: GLuint, expected: AttributeKind, found: AttributeKind },
+}
+
+impl fmt::Display for VertexInputIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VertexInputIssue::Missing { name, location } => write!(f, "shader input {} at location {} has no vertex attribute", name, location),
+            VertexInputIssue::Unused { location } => write!(f, "vertex attribute at location {} is not read by the shader", location),
+            VertexInputIssue::KindMismatch { name, location, expected, found } => {
+                write!(f, "shader input {} at location {} expects {:?} data but the attribute provides {:?}", name, location, expected, found)
+            }
+        }
+    }
+}
+
+/// How a shader input type maps onto locations: its column count, the kind of data each column is
+/// fetched as, and how many locations each column occupies (two for `dvec3`/`dvec4` columns).
+fn input_shape(gl_type: GLenum) -> (GLuint, AttributeKind, GLuint) {
+    match gl_type {
+        gl::INT | gl::INT_VEC2 | gl::INT_VEC3 | gl::INT_VEC4
+        | gl::UNSIGNED_INT | gl::UNSIGNED_INT_VEC2 | gl::UNSIGNED_INT_VEC3 | gl::UNSIGNED_INT_VEC4 => (1, AttributeKind::Integer, 1),
+        gl::DOUBLE | gl::DOUBLE_VEC2 => (1, AttributeKind::Double, 1),
+        gl::DOUBLE_VEC3 | gl::DOUBLE_VEC4 => (1, AttributeKind::Double, 2),
+        gl::FLOAT_MAT2 | gl::FLOAT_MAT2x3 | gl::FLOAT_MAT2x4 => (2, AttributeKind::Float, 1),
+        gl::FLOAT_MAT3 | gl::FLOAT_MAT3x2 | gl::FLOAT_MAT3x4 => (3, AttributeKind::Float, 1),
+        gl::FLOAT_MAT4 | gl::FLOAT_MAT4x2 | gl::FLOAT_MAT4x3 => (4, AttributeKind::Float, 1),
+        gl::DOUBLE_MAT2 => (2, AttributeKind::Double, 1),
+        gl::DOUBLE_MAT2x3 | gl::DOUBLE_MAT2x4 => (2, AttributeKind::Double, 2),
+        gl::DOUBLE_MAT3x2 => (3, AttributeKind::Double, 1),
+        gl::DOUBLE_MAT3 | gl::DOUBLE_MAT3x4 => (3, AttributeKind::Double, 2),
+        gl::DOUBLE_MAT4x2 => (4, AttributeKind::Double, 1),
+        gl::DOUBLE_MAT4 | gl::DOUBLE_MAT4x3 => (4, AttributeKind::Double, 2),
+        _ => (1, AttributeKind::Float, 1),
+    }
+}
+
+/// Compares a program's active inputs with the attributes set up to feed them. Component counts are
+/// not compared, since GL fills missing components with (0, 0, 0, 1) and ignores extra ones.
+pub fn check_vertex_inputs(inputs: &[AttributeInfo], attributes: &[(GLuint, AttributeKind)]) -> Vec<VertexInputIssue> {
+    let mut expected = BTreeMap::new();
+    for input in inputs.iter().filter(|input| input.location >= 0) {
+        let (columns, kind, locations_per_column) = input_shape(input.gl_type);
+        for column in 0..columns * input.size.max(1) as GLuint {
+            expected.insert(input.location as GLuint + column * locations_per_column, (input.name.as_str(), kind));
+        }
+    }
+
+    let provided: BTreeMap<GLuint, AttributeKind> = attributes.iter().copied().collect();
+    let mut issues = Vec::new();
+
+    for (location, (name, kind)) in &expected {
+        match provided.get(location) {
+            None => issues.push(VertexInputIssue::Missing { name: name.to_string(), location: *location }),
+            Some(found) if found != kind => issues.push(VertexInputIssue::KindMismatch {
+                name: name.to_string(),
+                location: *location,
+                expected: *kind,
+                found: *found,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for location in provided.keys().filter(|location| !expected.contains_key(location)) {
+        issues.push(VertexInputIssue::Unused { location: *location });
+    }
+
+    issues
+}
+
+impl ShaderProgram {
+    /// Checks that `attributes` feed exactly the inputs this program reads, with matching kinds.
+    pub fn validate_vertex_attributes(&self, attributes: &[VertexAttribute]) -> Result<(), ShaderError> {
+        let provided: Vec<(GLuint, AttributeKind)> = attributes.iter().map(|attribute| (attribute.index(), attribute.kind())).collect();
+        let issues = check_vertex_inputs(&self.reflection().attributes, &provided);
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ShaderError::VertexInputMismatch(issues))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str, gl_type: GLenum, location: GLint) -> AttributeInfo {
+        AttributeInfo { name: name.to_string(), gl_type, size: 1, location }
+    }
+
+    #[test]
+    fn reports_missing_unused_and_mismatched_inputs() {
+        let inputs = [
+            input("position", gl::FLOAT_VEC3, 0),
+            input("bones", gl::UNSIGNED_INT_VEC4, 1),
+            input("model", gl::FLOAT_MAT4, 2),
+            input("gl_VertexID", gl::INT, -1),
+        ];
+
+        let complete: Vec<_> = [(0, AttributeKind::Float), (1, AttributeKind::Integer)].into_iter()
+            .chain((2..6).map(|location| (location, AttributeKind::Float)))
+            .collect();
+        assert!(check_vertex_inputs(&inputs, &complete).is_empty());
+
+        let broken = [(0, AttributeKind::Float), (1, AttributeKind::Float), (2, AttributeKind::Float), (3, AttributeKind::Float), (4, AttributeKind::Float), (7, AttributeKind::Float)];
+        assert_eq!(check_vertex_inputs(&inputs, &broken), vec![
+            VertexInputIssue::KindMismatch { name: "bones".to_string(), location: 1, expected: AttributeKind::Integer, found: AttributeKind::Float },
+            VertexInputIssue::Missing { name: "model".to_string(), location: 5 },
+            VertexInputIssue::Unused { location: 7 },
+        ]);
+    }
+}
@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use gl::types::{GLenum, GLuint};
+use crate::rendering::context;
+use crate::rendering::pipeline::PipelineState;
+use crate::rendering::tracker::ResourceKind;
+
+/// What the wrappers last bound or enabled in one context. A missing entry means the state is
+/// unknown, so the next request for it always reaches GL.
+#[derive(Debug, Default)]
+struct GlState {
+    program: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    buffers: HashMap<GLenum, GLuint>,
+    active_texture: Option<GLuint>,
+    textures: HashMap<(GLuint, GLenum), GLuint>,
+    capabilities: HashMap<(GLenum, Option<GLuint>), bool>,
+    pipeline: Option<PipelineState>,
+}
+
+impl GlState {
+    fn set_program(&mut self, program: GLuint) -> bool {
+        self.program.replace(program) != Some(program)
+    }
+
+    fn set_vertex_array(&mut self, vertex_array: GLuint) -> bool {
+        let changed = self.vertex_array.replace(vertex_array) != Some(vertex_array);
+        if changed {
+            // The element buffer binding belongs to the VAO.
+            self.buffers.remove(&gl::ELEMENT_ARRAY_BUFFER);
+        }
+        changed
+    }
+
+    fn set_buffer(&mut self, target: GLenum, buffer: GLuint) -> bool {
+        self.buffers.insert(target, buffer) != Some(buffer)
+    }
+
+    fn set_active_texture(&mut self, unit: GLuint) -> bool {
+        self.active_texture.replace(unit) != Some(unit)
+    }
+
+    fn set_texture(&mut self, unit: GLuint, target: GLenum, texture: GLuint) -> bool {
+        self.textures.insert((unit, target), texture) != Some(texture)
+    }
+
+    fn set_capability(&mut self, capability: GLenum, enabled: bool) -> bool {
+        let changed = self.capabilities.insert((capability, None), enabled) != Some(enabled);
+        if changed {
+            // glEnable and glDisable set every index of an indexed capability.
+            self.capabilities.retain(|(cap, index), _| *cap != capability || index.is_none());
+        }
+        changed
+    }
+
+    fn set_capability_indexed(&mut self, capability: GLenum, index: GLuint, enabled: bool) -> bool {
+        let changed = self.capabilities.insert((capability, Some(index)), enabled) != Some(enabled);
+        if changed {
+            self.capabilities.remove(&(capability, None));
+        }
+        changed
+    }
+
+    /// Deleting an object unbinds it in the current context, and its name may be handed out again.
+    fn forget(&mut self, kind: ResourceKind, id: GLuint) {
+        match kind {
+            ResourceKind::Program => {
+                if self.program == Some(id) {
+                    self.program = None;
+                }
+            }
+            ResourceKind::VertexArray => {
+                if self.vertex_array == Some(id) {
+                    self.vertex_array = None;
+                    self.buffers.remove(&gl::ELEMENT_ARRAY_BUFFER);
+                }
+            }
+            ResourceKind::Buffer => self.buffers.retain(|_, buffer| *buffer != id),
+            ResourceKind::Texture => self.textures.retain(|_, texture| *texture != id),
+            ResourceKind::Framebuffer => {}
+        }
+    }
+}
+
+thread_local! {
+    static STATES: RefCell<HashMap<Option<u64>, GlState>> = RefCell::new(HashMap::new());
+}
+
+/// Runs `update` against the cache of the current context. Every context keeps a cache of its own,
+/// so switching between contexts loses nothing.
+fn with_state<R>(update: impl FnOnce(&mut GlState) -> R) -> R {
+    STATES.with(|states| {
+        let mut states = states.borrow_mut();
+        update(states.entry(context::current_context()).or_default())
+    })
+}
+
+pub fn use_program(program: GLuint) {
+    if with_state(|state| state.set_program(program)) {
+        unsafe {
+            gl::UseProgram(program);
+        }
+    }
+}
+
+pub fn bind_vertex_array(vertex_array: GLuint) {
+    if with_state(|state| state.set_vertex_array(vertex_array)) {
+        unsafe {
+            gl::BindVertexArray(vertex_array);
+        }
+    }
+}
+
+/// The VAO bound in the current context, asking GL only when the cache does not know it.
+pub(crate) fn vertex_array_binding() -> GLuint {
+    if let Some(vertex_array) = with_state(|state| state.vertex_array) {
+        return vertex_array;
+    }
+
+    let mut vertex_array = 0;
+    unsafe {
+        gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut vertex_array);
+    }
+    with_state(|state| state.set_vertex_array(vertex_array as GLuint));
+    vertex_array as GLuint
+}
+
+pub fn bind_buffer(target: GLenum, buffer: GLuint) {
+    if with_state(|state| state.set_buffer(target, buffer)) {
+        unsafe {
+            gl::BindBuffer(target, buffer);
+        }
+    }
+}
+
+/// Records a binding made as a side effect of another call, such as `glBindBufferBase` also
+/// binding the buffer to the generic `target`.
+pub(crate) fn record_buffer(target: GLenum, buffer: GLuint) {
+    with_state(|state| state.set_buffer(target, buffer));
+}
+
+pub fn active_texture(unit: GLuint) {
+    if with_state(|state| state.set_active_texture(unit)) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+        }
+    }
+}
+
+/// Binds `texture` to `target` on the active texture unit. If the active unit is unknown, unit 0 is
+/// made active first.
+pub fn bind_texture(target: GLenum, texture: GLuint) {
+    let unit = match with_state(|state| state.active_texture) {
+        Some(unit) => unit,
+        None => {
+            active_texture(0);
+            0
+        }
+    };
+    if with_state(|state| state.set_texture(unit, target, texture)) {
+        unsafe {
+            gl::BindTexture(target, texture);
+        }
+    }
+}
+
+/// Binds `texture` to `target` on texture `unit`, leaving unit 0 active afterwards without direct
+/// state access. `target` must be the texture's own target.
+pub fn bind_texture_unit(unit: GLuint, target: GLenum, texture: GLuint) {
+    if context::supports_dsa() {
+        if with_state(|state| state.set_texture(unit, target, texture)) {
+            unsafe {
+                gl::BindTextureUnit(unit, texture);
+            }
+        }
+        return;
+    }
+
+    active_texture(unit);
+    bind_texture(target, texture);
+    active_texture(0);
+}
+
+pub fn set_enabled(capability: GLenum, enabled: bool) {
+    if with_state(|state| state.set_capability(capability, enabled)) {
+        unsafe {
+            if enabled {
+                gl::Enable(capability);
+            } else {
+                gl::Disable(capability);
+            }
+        }
+    }
+}
+
+pub fn set_enabled_indexed(capability: GLenum, index: GLuint, enabled: bool) {
+    if with_state(|state| state.set_capability_indexed(capability, index, enabled)) {
+        unsafe {
+            if enabled {
+                gl::Enablei(capability, index);
+            } else {
+                gl::Disablei(capability, index);
+            }
+        }
+    }
+}
+
+/// Whether the wrappers left `capability` enabled. Capabilities they never touched count as disabled.
+pub fn is_enabled(capability: GLenum) -> bool {
+    with_state(|state| state.capabilities.get(&(capability, None)) == Some(&true))
+}
+
+pub fn enable(capability: GLenum) {
+    set_enabled(capability, true);
+}
+
+pub fn disable(capability: GLenum) {
+    set_enabled(capability, false);
+}
+
+/// Forgets everything cached for the current context. Call this after GL state was changed
+/// outside the wrappers, e.g. by another library sharing the context.
+pub fn invalidate() {
+    with_state(|state| *state = GlState::default());
+}
+
+/// Drops the cache of a context that was destroyed.
+pub(crate) fn forget_context(context_id: u64) {
+    STATES.with(|states| states.borrow_mut().remove(&Some(context_id)));
+}
+
+/// The pipeline applied last, if nothing touched its state since. `store_pipeline` puts it back.
+pub(crate) fn take_pipeline() -> Option<PipelineState> {
+    with_state(|state| state.pipeline.take())
+}
+
+/// The pipeline applied last, if nothing touched its state since, leaving it in the cache.
+pub(crate) fn pipeline() -> Option<PipelineState> {
+    with_state(|state| state.pipeline)
+}
+
+pub(crate) fn store_pipeline(pipeline: PipelineState) {
+    with_state(|state| state.pipeline = Some(pipeline));
+}
+
+/// Marks a buffer binding as unknown after it was changed by name, e.g. through
+/// `glVertexArrayElementBuffer` on a VAO that may be bound.
+pub(crate) fn forget_binding(target: GLenum) {
+    with_state(|state| state.buffers.remove(&target));
+}
+
+pub(crate) fn forget(kind: ResourceKind, id: GLuint) {
+    with_state(|state| state.forget(kind, id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_redundant_changes() {
+        let mut state = GlState::default();
+        assert!(state.set_program(3));
+        assert!(!state.set_program(3));
+
+        assert!(state.set_vertex_array(1));
+        assert!(state.set_buffer(gl::ELEMENT_ARRAY_BUFFER, 5));
+        assert!(!state.set_buffer(gl::ELEMENT_ARRAY_BUFFER, 5));
+        assert!(state.set_vertex_array(2));
+        assert!(state.set_buffer(gl::ELEMENT_ARRAY_BUFFER, 5));
+
+        assert!(state.set_texture(0, gl::TEXTURE_2D, 7));
+        assert!(!state.set_texture(0, gl::TEXTURE_2D, 7));
+        assert!(state.set_texture(1, gl::TEXTURE_2D, 7));
+        state.forget(ResourceKind::Texture, 7);
+        assert!(state.set_texture(0, gl::TEXTURE_2D, 7));
+
+        assert!(state.set_capability(gl::DEPTH_TEST, true));
+        assert!(!state.set_capability(gl::DEPTH_TEST, true));
+        assert!(state.set_capability(gl::DEPTH_TEST, false));
+
+        assert!(state.set_capability(gl::BLEND, true));
+        assert!(state.set_capability_indexed(gl::BLEND, 1, false));
+        assert!(state.set_capability(gl::BLEND, true));
+        assert!(state.set_capability_indexed(gl::BLEND, 1, false));
+    }
+}
@@ -1,100 +1,105 @@
 use std::ffi::c_void;
+use std::path::PathBuf;
 use gl::types::*;
 use image::ColorType;
 use image::io::Reader as ImageReader;
+use crate::errors::TextureError;
+use crate::rendering::context::{self, ContextGuard};
+use crate::rendering::state;
+use crate::rendering::tracker::{self, ResourceKind};
 
 pub struct Texture {
     id: GLuint,
     width: u32,
     height: u32,
     parameters: Vec<TextureParameter>,
+    context: ContextGuard,
 }
 
 impl Texture {
     pub fn new(texture_data: Vec<u8>, color_format: GLenum, color_format_type: GLenum, width: u32, height: u32, parameters: Vec<TextureParameter>) -> Texture {
         let mut id = 0;
         unsafe {
-            gl::GenTextures(1, &mut id);
-            gl::BindTexture(gl::TEXTURE_2D, id);
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                color_format as GLint,
-                width as GLsizei,
-                height as GLsizei,
-                0,
-                color_format,
-                color_format_type,
-                texture_data.as_ptr() as *const c_void,
-            );
-
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            if context::supports_dsa() {
+                gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id);
+                gl::TextureStorage2D(id, mip_levels(width, height), sized_format(color_format), width as GLsizei, height as GLsizei);
+                gl::TextureSubImage2D(
+                    id,
+                    0,
+                    0,
+                    0,
+                    width as GLsizei,
+                    height as GLsizei,
+                    color_format,
+                    color_format_type,
+                    texture_data.as_ptr() as *const c_void,
+                );
+
+                gl::GenerateTextureMipmap(id);
+            } else {
+                gl::GenTextures(1, &mut id);
+                state::bind_texture(gl::TEXTURE_2D, id);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    color_format as GLint,
+                    width as GLsizei,
+                    height as GLsizei,
+                    0,
+                    color_format,
+                    color_format_type,
+                    texture_data.as_ptr() as *const c_void,
+                );
+
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+                state::bind_texture(gl::TEXTURE_2D, 0);
+            }
         }
 
-        Texture { id, width, height, parameters }
-    }
+        // The full mip chain adds about a third on top of the base level.
+        tracker::register(ResourceKind::Texture, id, texture_data.len() * 4 / 3);
 
-    pub fn from(file_path: &str) -> Result<Texture, String> {
-        let reader_option = ImageReader::open(file_path);
+        Texture { id, width, height, parameters, context: ContextGuard::current() }
+    }
 
-        match reader_option {
-            Ok(reader) => {
-                let texture_result = reader.decode();
+    pub fn from(file_path: &str) -> Result<Texture, TextureError> {
+        let reader = ImageReader::open(file_path)
+            .map_err(|source| TextureError::Io { path: PathBuf::from(file_path), source })?;
+        let texture = reader.decode()?;
+        let ct = texture.color();
+
+        let gl_ct = match ct {
+            ColorType::Rgb8 => gl::RGB,
+            ColorType::Rgba8 => gl::RGBA,
+            ColorType::Rgb16 => gl::RGB16,
+            ColorType::Rgba16 => gl::RGBA16,
+            ColorType::Rgb32F => gl::RGB32F,
+            ColorType::Rgba32F => gl::RGBA32F,
+            _ => return Err(TextureError::UnsupportedColorType(ct)),
+        };
+        let gl_ct_type = gl::UNSIGNED_BYTE;
+
+        let width = texture.width();
+        let height = texture.height();
+        let data = texture.as_bytes();
+
+        Ok(Texture::new(data.to_vec(), gl_ct, gl_ct_type, width, height, Vec::new()))
+    }
 
-                if texture_result.is_err() {
-                    return Err(texture_result.err().unwrap().to_string())
-                }
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
 
-                let texture = texture_result.unwrap();
-                let ct = texture.color();
-
-                let mut gl_ct = gl::RGB;
-                let gl_ct_type = gl::UNSIGNED_BYTE;
-
-                match ct {
-                    ColorType::L8 => {
-                        return Err("Unsupported color format!".to_string());
-                    }
-                    ColorType::La8 => {
-                        return Err("Unsupported color format!".to_string());
-                    }
-                    ColorType::Rgb8 => {}
-                    ColorType::Rgba8 => {
-                        gl_ct = gl::RGBA;
-                    }
-                    ColorType::L16 => {
-                        return Err("Unsupported color format!".to_string());
-                    }
-                    ColorType::La16 => {
-                        return Err("Unsupported color format!".to_string());
-                    }
-                    ColorType::Rgb16 => {
-                        gl_ct = gl::RGB16;
-                    }
-                    ColorType::Rgba16 => {
-                        gl_ct = gl::RGBA16;
-                    }
-                    ColorType::Rgb32F => {
-                        gl_ct = gl::RGB32F;
-                    }
-                    ColorType::Rgba32F => {
-                        gl_ct = gl::RGBA32F;
-                    }
-                    _ => {
-                        return Err("Unsupported color format!".to_string());
-                    }
-                }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
 
-                let width = texture.width();
-                let height = texture.height();
-                let data = texture.as_bytes();
+    pub fn height(&self) -> u32 {
+        self.height
+    }
 
-                Ok(Texture::new(data.to_vec(), gl_ct, gl_ct_type, width, height, Vec::new()))
-            }
-            Err(val) => {
-                Err(val.to_string())
-            }
-        }
+    pub fn set_label(&self, label: &str) {
+        tracker::set_label(ResourceKind::Texture, self.id, label);
     }
 
     pub fn with_parameter(&mut self, parameter: TextureParameter) {
@@ -103,19 +108,74 @@ impl Texture {
 
     pub fn apply_parameters(&self) {
         for parameter in &self.parameters {
-            parameter.apply();
+            parameter.apply_to(self.id);
         }
     }
 
     pub fn bind(&self) {
+        state::bind_texture(gl::TEXTURE_2D, self.id);
+    }
+
+    pub fn bind_to_unit(&self, unit: GLuint) {
+        state::bind_texture_unit(unit, gl::TEXTURE_2D, self.id);
+    }
+
+    /// Binds one mip `level` to image unit `unit` for `imageLoad`/`imageStore` in shaders.
+    /// `format` must be a sized internal format such as `gl::RGBA8` or `gl::R32F`.
+    pub fn bind_image(&self, unit: GLuint, level: GLint, access: ImageAccess, format: GLenum) {
         unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::BindImageTexture(unit, self.id, level, gl::FALSE, 0, access.gl_enum(), format);
         }
     }
 
     pub fn unbind(&self) {
-        unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, 0);
+        state::bind_texture(gl::TEXTURE_2D, 0);
+    }
+}
+
+/// Immutable storage needs a sized format; unsized ones are given eight bits per channel.
+fn sized_format(format: GLenum) -> GLenum {
+    match format {
+        gl::RED => gl::R8,
+        gl::RG => gl::RG8,
+        gl::RGB => gl::RGB8,
+        gl::RGBA => gl::RGBA8,
+        sized => sized,
+    }
+}
+
+/// The length of the full mip chain down to 1x1.
+fn mip_levels(width: u32, height: u32) -> GLsizei {
+    (32 - width.max(height).max(1).leading_zeros()) as GLsizei
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        if self.context.can_delete(ResourceKind::Texture, self.id) {
+            unsafe {
+                gl::DeleteTextures(1, &self.id);
+            }
+            tracker::unregister(ResourceKind::Texture, self.id);
+            state::forget(ResourceKind::Texture, self.id);
+        } else if self.context.is_destroyed() {
+            tracker::unregister(ResourceKind::Texture, self.id);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl ImageAccess {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            ImageAccess::ReadOnly => gl::READ_ONLY,
+            ImageAccess::WriteOnly => gl::WRITE_ONLY,
+            ImageAccess::ReadWrite => gl::READ_WRITE,
         }
     }
 }
@@ -136,6 +196,24 @@ impl TextureParameter {
         TextureParameter { r#type, parameter, parameter_value_i: None, parameter_value_fv: Option::from(parameter_value) }
     }
 
+    /// Sets the parameter on `texture`, by name with direct state access or by binding it otherwise.
+    pub fn apply_to(&self, texture: GLuint) {
+        if !context::supports_dsa() {
+            state::bind_texture(self.r#type, texture);
+            self.apply();
+            return;
+        }
+
+        unsafe {
+            match (self.parameter_value_i, &self.parameter_value_fv) {
+                (Some(val), _) => gl::TextureParameteri(texture, self.parameter, val),
+                (None, Some(fv)) => gl::TextureParameterfv(texture, self.parameter, fv.as_ptr()),
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Sets the parameter on the texture bound to the parameter's target.
     pub fn apply(&self) {
         match self.parameter_value_i {
             Some(val) => {
@@ -0,0 +1,245 @@
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use gl::types::GLuint;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    VertexArray,
+    Buffer,
+    Texture,
+    Program,
+    Framebuffer,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceKind::VertexArray => write!(f, "vertex array"),
+            ResourceKind::Buffer => write!(f, "buffer"),
+            ResourceKind::Texture => write!(f, "texture"),
+            ResourceKind::Program => write!(f, "program"),
+            ResourceKind::Framebuffer => write!(f, "framebuffer"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResourceRecord {
+    pub kind: ResourceKind,
+    pub id: GLuint,
+    pub size: usize,
+    pub label: Option<String>,
+    pub backtrace: Option<String>,
+    pub created: Instant,
+}
+
+impl ResourceRecord {
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TrackerConfig {
+    capture_backtraces: bool,
+    size_limit: Option<usize>,
+}
+
+impl TrackerConfig {
+    pub fn new() -> TrackerConfig {
+        TrackerConfig::default()
+    }
+
+    /// Records where each resource was created. Capturing a backtrace per object is slow, so this is
+    /// meant for tracking down a specific leak rather than for always-on use.
+    pub fn with_backtraces(mut self) -> TrackerConfig {
+        self.capture_backtraces = true;
+        self
+    }
+
+    /// Flags resources larger than `bytes` as oversized in reports.
+    pub fn with_size_limit(mut self, bytes: usize) -> TrackerConfig {
+        self.size_limit = Some(bytes);
+        self
+    }
+}
+
+struct Tracker {
+    config: TrackerConfig,
+    resources: BTreeMap<(ResourceKind, GLuint), ResourceRecord>,
+}
+
+thread_local! {
+    static TRACKER: RefCell<Option<Tracker>> = const { RefCell::new(None) };
+}
+
+/// Starts recording GL objects created on this thread. Objects created before this call are not tracked.
+pub fn enable(config: TrackerConfig) {
+    TRACKER.with(|tracker| {
+        *tracker.borrow_mut() = Some(Tracker { config, resources: BTreeMap::new() });
+    });
+}
+
+pub fn disable() {
+    TRACKER.with(|tracker| *tracker.borrow_mut() = None);
+}
+
+pub fn is_enabled() -> bool {
+    TRACKER.with(|tracker| tracker.borrow().is_some())
+}
+
+fn with_tracker(f: impl FnOnce(&mut Tracker)) {
+    TRACKER.with(|tracker| {
+        if let Some(tracker) = tracker.borrow_mut().as_mut() {
+            f(tracker);
+        }
+    });
+}
+
+/// Records a newly created object. Renderia's own types call this themselves; it is public so
+/// objects created elsewhere, such as framebuffers, can be tracked too.
+pub fn register(kind: ResourceKind, id: GLuint, size: usize) {
+    with_tracker(|tracker| {
+        let backtrace = tracker.config.capture_backtraces.then(|| Backtrace::force_capture().to_string());
+        tracker.resources.insert((kind, id), ResourceRecord {
+            kind,
+            id,
+            size,
+            label: None,
+            backtrace,
+            created: Instant::now(),
+        });
+    });
+}
+
+pub fn unregister(kind: ResourceKind, id: GLuint) {
+    with_tracker(|tracker| {
+        tracker.resources.remove(&(kind, id));
+    });
+}
+
+/// Moves a record to a new object name, e.g. when a program is relinked in place, keeping its label and age.
+pub fn replace_id(kind: ResourceKind, old_id: GLuint, new_id: GLuint) {
+    with_tracker(|tracker| {
+        if let Some(mut record) = tracker.resources.remove(&(kind, old_id)) {
+            record.id = new_id;
+            tracker.resources.insert((kind, new_id), record);
+        }
+    });
+}
+
+pub fn set_size(kind: ResourceKind, id: GLuint, size: usize) {
+    with_tracker(|tracker| {
+        if let Some(record) = tracker.resources.get_mut(&(kind, id)) {
+            record.size = size;
+        }
+    });
+}
+
+pub fn set_label(kind: ResourceKind, id: GLuint, label: &str) {
+    with_tracker(|tracker| {
+        if let Some(record) = tracker.resources.get_mut(&(kind, id)) {
+            record.label = Some(label.to_string());
+        }
+    });
+}
+
+/// A snapshot of every tracked object that is still alive.
+pub fn report() -> ResourceReport {
+    TRACKER.with(|tracker| match tracker.borrow().as_ref() {
+        Some(tracker) => ResourceReport {
+            resources: tracker.resources.values().cloned().collect(),
+            size_limit: tracker.config.size_limit,
+        },
+        None => ResourceReport { resources: Vec::new(), size_limit: None },
+    })
+}
+
+/// Panics with a report of every live object. Call it once everything should have been dropped,
+/// e.g. at the end of a test.
+pub fn assert_no_leaks() {
+    let report = report();
+    if !report.is_empty() {
+        panic!("{} GPU resources are still alive:\n{}", report.resources.len(), report);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResourceReport {
+    pub resources: Vec<ResourceRecord>,
+    pub size_limit: Option<usize>,
+}
+
+impl ResourceReport {
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    pub fn total_size(&self) -> usize {
+        self.resources.iter().map(|record| record.size).sum()
+    }
+
+    pub fn oversized(&self) -> impl Iterator<Item = &ResourceRecord> {
+        let limit = self.size_limit.unwrap_or(usize::MAX);
+        self.resources.iter().filter(move |record| record.size > limit)
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} live resources, {} bytes", self.resources.len(), self.total_size())?;
+
+        for record in &self.resources {
+            write!(f, "  {} {}", record.kind, record.id)?;
+            if let Some(label) = &record.label {
+                write!(f, " \"{}\"", label)?;
+            }
+            write!(f, ": {} bytes, alive for {:.1?}", record.size, record.age())?;
+            if self.size_limit.is_some_and(|limit| record.size > limit) {
+                write!(f, " (oversized)")?;
+            }
+            writeln!(f)?;
+
+            if let Some(backtrace) = &record.backtrace {
+                for line in backtrace.lines() {
+                    writeln!(f, "      {}", line)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_live_and_oversized_resources() {
+        register(ResourceKind::Buffer, 1, 16);
+        assert!(report().is_empty());
+
+        enable(TrackerConfig::new().with_size_limit(1024));
+        register(ResourceKind::Buffer, 1, 16);
+        register(ResourceKind::Texture, 1, 0);
+        set_size(ResourceKind::Texture, 1, 4096);
+        set_label(ResourceKind::Texture, 1, "wall");
+        register(ResourceKind::Framebuffer, 2, 0);
+        unregister(ResourceKind::Framebuffer, 2);
+
+        let report = report();
+        assert_eq!(report.resources.len(), 2);
+        assert_eq!(report.total_size(), 4112);
+        assert_eq!(report.oversized().map(|record| record.id).collect::<Vec<_>>(), vec![1]);
+        assert!(report.to_string().contains("texture 1 \"wall\": 4096 bytes"));
+
+        unregister(ResourceKind::Buffer, 1);
+        unregister(ResourceKind::Texture, 1);
+        assert_no_leaks();
+        disable();
+    }
+}
@@ -0,0 +1,199 @@
+use bytemuck::Pod;
+use gl::types::*;
+use bytemuck::Zeroable;
+use glam::{DVec2, DVec3, DVec4, IVec2, IVec3, IVec4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+pub use renderia_derive::Vertex;
+
+/// How the shader sees an attribute: converted to floats (`glVertexAttribPointer`), as integers
+/// (`glVertexAttribIPointer`) or as doubles (`glVertexAttribLPointer`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeKind {
+    Float,
+    Integer,
+    Double,
+}
+
+impl AttributeKind {
+    /// Integer data that is normalized, or explicitly requested as floats, is converted on fetch.
+    pub const fn resolve(natural: AttributeKind, as_float: bool) -> AttributeKind {
+        match (natural, as_float) {
+            (AttributeKind::Integer, true) => AttributeKind::Float,
+            (kind, _) => kind,
+        }
+    }
+}
+
+/// Where one attribute lives inside a vertex. `offset` is in bytes from the start of the vertex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexAttributeLayout {
+    pub location: GLuint,
+    pub components: GLint,
+    pub gl_type: GLenum,
+    pub kind: AttributeKind,
+    pub normalized: bool,
+    pub offset: usize,
+}
+
+/// A vertex type whose attribute layout is known at compile time. Derive it on a `#[repr(C)]` struct;
+/// fields get consecutive locations unless marked `#[vertex(location = N)]` and padding fields can
+/// be marked `#[vertex(skip)]`. Integer fields reach the shader as integers unless marked
+/// `#[vertex(normalized)]` or `#[vertex(float)]`.
+pub trait Vertex: Pod {
+    const ATTRIBUTES: &'static [VertexAttributeLayout];
+
+    fn stride() -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+/// A field type that can feed a vertex attribute.
+pub trait VertexComponent {
+    const COMPONENTS: GLint;
+    const GL_TYPE: GLenum;
+    const KIND: AttributeKind;
+    /// The number of attribute locations the field occupies; `dvec3` and `dvec4` take two.
+    const LOCATIONS: GLuint = 1;
+}
+
+/// Scalar components, which can also be grouped into arrays of up to four.
+pub trait VertexScalar: VertexComponent {}
+
+macro_rules! impl_vertex_component {
+    ($($ty:ty => $components:expr, $gl_type:expr, $kind:ident $(, $locations:expr)?);* $(;)?) => {
+        $(
+            impl VertexComponent for $ty {
+                const COMPONENTS: GLint = $components;
+                const GL_TYPE: GLenum = $gl_type;
+                const KIND: AttributeKind = AttributeKind::$kind;
+                $(const LOCATIONS: GLuint = $locations;)?
+            }
+        )*
+    };
+}
+
+impl_vertex_component!(
+    f32 => 1, gl::FLOAT, Float;
+    f64 => 1, gl::DOUBLE, Double;
+    i8 => 1, gl::BYTE, Integer;
+    u8 => 1, gl::UNSIGNED_BYTE, Integer;
+    i16 => 1, gl::SHORT, Integer;
+    u16 => 1, gl::UNSIGNED_SHORT, Integer;
+    i32 => 1, gl::INT, Integer;
+    u32 => 1, gl::UNSIGNED_INT, Integer;
+);
+
+impl VertexScalar for f32 {}
+impl VertexScalar for f64 {}
+impl VertexScalar for i8 {}
+impl VertexScalar for u8 {}
+impl VertexScalar for i16 {}
+impl VertexScalar for u16 {}
+impl VertexScalar for i32 {}
+impl VertexScalar for u32 {}
+
+impl_vertex_component!(
+    Vec2 => 2, gl::FLOAT, Float;
+    Vec3 => 3, gl::FLOAT, Float;
+    Vec4 => 4, gl::FLOAT, Float;
+    DVec2 => 2, gl::DOUBLE, Double;
+    DVec3 => 3, gl::DOUBLE, Double, 2;
+    DVec4 => 4, gl::DOUBLE, Double, 2;
+    IVec2 => 2, gl::INT, Integer;
+    IVec3 => 3, gl::INT, Integer;
+    IVec4 => 4, gl::INT, Integer;
+    UVec2 => 2, gl::UNSIGNED_INT, Integer;
+    UVec3 => 3, gl::UNSIGNED_INT, Integer;
+    UVec4 => 4, gl::UNSIGNED_INT, Integer;
+    PackedNormal => 4, gl::INT_2_10_10_10_REV, Float;
+    PackedColor => 4, gl::UNSIGNED_INT_2_10_10_10_REV, Float;
+);
+
+impl<T: VertexScalar, const N: usize> VertexComponent for [T; N] {
+    const COMPONENTS: GLint = {
+        assert!(N >= 1 && N <= 4, "vertex attributes have between one and four components");
+        N as GLint
+    };
+    const GL_TYPE: GLenum = T::GL_TYPE;
+    const KIND: AttributeKind = T::KIND;
+    const LOCATIONS: GLuint = if matches!(T::KIND, AttributeKind::Double) && N > 2 { 2 } else { 1 };
+}
+
+/// Four signed components packed into 32 bits as `INT_2_10_10_10_REV`: 10 bits each for x, y and z
+/// and 2 for w. Mark the field `#[vertex(normalized)]` to read it as a `vec4` in [-1, 1].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Pod, Zeroable)]
+pub struct PackedNormal(pub u32);
+
+impl PackedNormal {
+    pub fn new(value: Vec4) -> PackedNormal {
+        let pack = |component: f32, bits: u32, max: f32| {
+            let mask = (1 << bits) - 1;
+            ((component.clamp(-1.0, 1.0) * max).round() as i32 as u32) & mask
+        };
+        PackedNormal(pack(value.x, 10, 511.0) | pack(value.y, 10, 511.0) << 10 | pack(value.z, 10, 511.0) << 20 | pack(value.w, 2, 1.0) << 30)
+    }
+
+    pub fn from_normal(normal: Vec3) -> PackedNormal {
+        PackedNormal::new(normal.extend(0.0))
+    }
+}
+
+/// Four unsigned components packed into 32 bits as `UNSIGNED_INT_2_10_10_10_REV`, e.g. an HDR-ish
+/// colour with 10 bits per channel and 2 bits of alpha. Mark the field `#[vertex(normalized)]`.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Pod, Zeroable)]
+pub struct PackedColor(pub u32);
+
+impl PackedColor {
+    pub fn new(value: Vec4) -> PackedColor {
+        let pack = |component: f32, max: f32| (component.clamp(0.0, 1.0) * max).round() as u32;
+        PackedColor(pack(value.x, 1023.0) | pack(value.y, 1023.0) << 10 | pack(value.z, 1023.0) << 20 | pack(value.w, 3.0) << 30)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+    struct TestVertex {
+        position: Vec3,
+        #[vertex(location = 4)]
+        uv: Vec2,
+        #[vertex(normalized)]
+        color: [u8; 4],
+        bones: [u8; 4],
+        #[vertex(normalized)]
+        normal: PackedNormal,
+        weights: DVec3,
+        material: u32,
+        #[vertex(skip)]
+        _padding: u32,
+    }
+
+    #[test]
+    fn derives_vertex_layout() {
+        let layout = |location, components, gl_type, kind, normalized, offset| {
+            VertexAttributeLayout { location, components, gl_type, kind, normalized, offset }
+        };
+
+        assert_eq!(TestVertex::stride(), 64);
+        assert_eq!(TestVertex::ATTRIBUTES, &[
+            layout(0, 3, gl::FLOAT, AttributeKind::Float, false, 0),
+            layout(4, 2, gl::FLOAT, AttributeKind::Float, false, 12),
+            layout(5, 4, gl::UNSIGNED_BYTE, AttributeKind::Float, true, 20),
+            layout(6, 4, gl::UNSIGNED_BYTE, AttributeKind::Integer, false, 24),
+            layout(7, 4, gl::INT_2_10_10_10_REV, AttributeKind::Float, true, 28),
+            layout(8, 3, gl::DOUBLE, AttributeKind::Double, false, 32),
+            layout(10, 1, gl::UNSIGNED_INT, AttributeKind::Integer, false, 56),
+        ]);
+    }
+
+    #[test]
+    fn packs_2_10_10_10() {
+        assert_eq!(PackedNormal::from_normal(Vec3::new(1.0, -1.0, 0.0)).0, 0x1ff | 0x201 << 10);
+        assert_eq!(PackedColor::new(Vec4::ONE).0, u32::MAX);
+    }
+}
@@ -1,14 +1,17 @@
 use glfw::{Context, GlfwReceiver, WindowEvent};
+use crate::errors::WindowError;
+use crate::rendering::context;
 
 pub struct Window {
     glfw: glfw::Glfw,
     window_handle: glfw::PWindow,
     events: GlfwReceiver<(f64, WindowEvent)>,
+    context_id: u64,
 }
 
 impl Window {
-    pub fn new(width: u32, height: u32, title: &str) -> Window {
-        let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
+    pub fn new(width: u32, height: u32, title: &str) -> Result<Window, WindowError> {
+        let mut glfw = glfw::init(glfw::log_errors)?;
         glfw.window_hint(glfw::WindowHint::ContextVersion(4, 6));
         glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
         #[cfg(target_os = "macos")]
@@ -16,21 +19,38 @@ impl Window {
 
         let (mut window, events) = glfw
             .create_window(width, height, title, glfw::WindowMode::Windowed)
-            .expect("Failed to create window!");
+            .ok_or(WindowError::Creation)?;
 
         window.set_framebuffer_size_polling(true);
         window.set_key_polling(true);
 
-        Window {
+        Ok(Window {
             glfw,
             window_handle: window,
             events,
+            context_id: context::allocate_context_id(),
+        })
+    }
+
+    pub fn init_gl(&mut self) -> Result<(), WindowError> {
+        self.make_current();
+        gl::load_with(|s| self.window_handle.get_proc_address(s) as *const _);
+
+        if !gl::GetString::is_loaded() {
+            return Err(WindowError::Context);
         }
+        context::set_capabilities(self.context_id, context::Capabilities::detect());
+
+        Ok(())
     }
 
-    pub fn init_gl(&mut self) {
+    pub fn make_current(&mut self) {
         self.window_handle.make_current();
-        gl::load_with(|s| self.window_handle.get_proc_address(s) as *const _);
+        context::make_current(self.context_id);
+    }
+
+    pub fn context_id(&self) -> u64 {
+        self.context_id
     }
 
     pub fn should_close(&self) -> bool {
@@ -53,4 +73,10 @@ impl Window {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        context::destroy(self.context_id);
+    }
+}